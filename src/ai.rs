@@ -0,0 +1,482 @@
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+use crate::{collision::{self, BodyT}, entities::{self, EntityIdT, EntityT}, game_state::GameStateT, movers::{self, TriggerT}, player::{self, PlayerT}, renderer::SectorT, typedefs::Vec2T, weapons::{self, ProjectileInfoT}};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+const MELEE_RANGE: f64 = 64.0;
+const MISSILE_RANGE: f64 = 2048.0;
+const REACTION_TIME: i32 = 8; // Tics que espera un monstruo recien despierto antes de disparar
+const SPREAD: f64 = 0.37;     // Desvio maximo de un disparo (~21 grados, como P_Random()-P_Random() en Doom)
+pub const NUM_DIRS: usize = 8;    // Los monstruos caminan en 8 direcciones
+const MAX_STATE_CHAIN: usize = 8; // Cambios de estado seguidos en un mismo tic
+const SOUND_BLOCKS: u32 = 2;  // Paredes block_sound que cortan el ruido (una sola se atraviesa)
+
+// Daño de un ataque: (1..=sides) * mult, como las tiradas de Doom
+#[derive(Clone, Copy)]
+pub struct DiceT {
+    pub sides: i32,
+    pub mult: i32,
+}
+
+#[derive(Clone, Copy)]
+pub enum ActionT {
+    Look,             // Busca al jugador (o espera a oirlo)
+    Chase,            // Camina hacia el jugador y decide si atacar
+    FaceTarget,
+    Melee(DiceT),
+    Hitscan(i32, DiceT), // Disparos instantaneos (cantidad y daño de cada uno)
+    Projectile(&'static ProjectileInfoT),
+}
+
+// Cuadro de un estado: sprite a mostrar, duracion (-1 = para siempre) y accion al entrar
+pub struct FrameT {
+    pub frame: char,
+    tics: i32,
+    action: Option<ActionT>,
+}
+
+const fn fr(frame: char, tics: i32, action: Option<ActionT>) -> FrameT {
+    FrameT { frame, tics, action }
+}
+
+const LOOK: Option<ActionT> = Some(ActionT::Look);
+const CHASE: Option<ActionT> = Some(ActionT::Chase);
+const FACE: Option<ActionT> = Some(ActionT::FaceTarget);
+
+// Tablas de estados de cada monstruo (cuadros y tiempos de Doom)
+static MONSTERS: [MonsterInfoT; 4] = [
+    MonsterInfoT {
+        sprite: "POSS", health: 20, speed: 8.0, pain_chance: 200,
+        idle: &[fr('A', 10, LOOK), fr('B', 10, LOOK)],
+        see: &[fr('A', 4, FACE)],
+        chase: &[fr('A', 4, CHASE), fr('A', 4, CHASE), fr('B', 4, CHASE), fr('B', 4, CHASE),
+                 fr('C', 4, CHASE), fr('C', 4, CHASE), fr('D', 4, CHASE), fr('D', 4, CHASE)],
+        melee: &[],
+        missile: &[fr('E', 10, FACE), fr('F', 8, Some(ActionT::Hitscan(1, DiceT { sides: 5, mult: 3 }))), fr('E', 8, None)],
+        pain: &[fr('G', 3, None), fr('G', 3, None)],
+        death: &[fr('H', 5, None), fr('I', 5, None), fr('J', 5, None), fr('K', 5, None), fr('L', -1, None)],
+    },
+    MonsterInfoT {
+        sprite: "SPOS", health: 30, speed: 8.0, pain_chance: 170,
+        idle: &[fr('A', 10, LOOK), fr('B', 10, LOOK)],
+        see: &[fr('A', 3, FACE)],
+        chase: &[fr('A', 3, CHASE), fr('A', 3, CHASE), fr('B', 3, CHASE), fr('B', 3, CHASE),
+                 fr('C', 3, CHASE), fr('C', 3, CHASE), fr('D', 3, CHASE), fr('D', 3, CHASE)],
+        melee: &[],
+        missile: &[fr('E', 10, FACE), fr('F', 10, Some(ActionT::Hitscan(3, DiceT { sides: 5, mult: 3 }))), fr('E', 10, None)],
+        pain: &[fr('G', 3, None), fr('G', 3, None)],
+        death: &[fr('H', 5, None), fr('I', 5, None), fr('J', 5, None), fr('K', 5, None), fr('L', -1, None)],
+    },
+    MonsterInfoT {
+        sprite: "TROO", health: 60, speed: 8.0, pain_chance: 200,
+        idle: &[fr('A', 10, LOOK), fr('B', 10, LOOK)],
+        see: &[fr('A', 3, FACE)],
+        chase: &[fr('A', 3, CHASE), fr('A', 3, CHASE), fr('B', 3, CHASE), fr('B', 3, CHASE),
+                 fr('C', 3, CHASE), fr('C', 3, CHASE), fr('D', 3, CHASE), fr('D', 3, CHASE)],
+        melee: &[fr('E', 8, FACE), fr('F', 8, FACE), fr('G', 6, Some(ActionT::Melee(DiceT { sides: 8, mult: 3 })))],
+        missile: &[fr('E', 8, FACE), fr('F', 8, FACE), fr('G', 6, Some(ActionT::Projectile(&weapons::IMP_BALL)))],
+        pain: &[fr('H', 2, None), fr('H', 2, None)],
+        death: &[fr('I', 8, None), fr('J', 8, None), fr('K', 6, None), fr('L', 6, None), fr('M', -1, None)],
+    },
+    MonsterInfoT {
+        sprite: "SARG", health: 150, speed: 10.0, pain_chance: 180,
+        idle: &[fr('A', 10, LOOK), fr('B', 10, LOOK)],
+        see: &[fr('A', 2, FACE)],
+        chase: &[fr('A', 2, CHASE), fr('A', 2, CHASE), fr('B', 2, CHASE), fr('B', 2, CHASE),
+                 fr('C', 2, CHASE), fr('C', 2, CHASE), fr('D', 2, CHASE), fr('D', 2, CHASE)],
+        melee: &[fr('E', 8, FACE), fr('F', 8, FACE), fr('G', 8, Some(ActionT::Melee(DiceT { sides: 10, mult: 4 })))],
+        missile: &[],
+        pain: &[fr('H', 2, None), fr('H', 2, None)],
+        death: &[fr('I', 8, None), fr('J', 8, None), fr('K', 4, None), fr('L', 4, None), fr('M', 4, None), fr('N', -1, None)],
+    },
+];
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AiStateT {
+    Idle,    // Quieto esperando ver u oir al jugador
+    See,     // Acaba de despertar
+    Chase,
+    Melee,
+    Missile,
+    Pain,
+    Death,
+}
+
+pub struct MonsterInfoT {
+    pub sprite: &'static str,
+    pub health: i32,
+    speed: f64,       // Distancia de cada paso
+    pain_chance: i32, // Sobre 256
+    idle: &'static [FrameT],
+    see: &'static [FrameT],
+    chase: &'static [FrameT],
+    melee: &'static [FrameT],   // Vacio si no pega de cerca
+    missile: &'static [FrameT], // Vacio si no ataca a distancia
+    pain: &'static [FrameT],
+    death: &'static [FrameT],
+}
+    impl MonsterInfoT {
+        pub fn frames(&self, state: AiStateT) -> &'static [FrameT] {
+            match state {
+                AiStateT::Idle => self.idle,
+                AiStateT::See => self.see,
+                AiStateT::Chase => self.chase,
+                AiStateT::Melee => self.melee,
+                AiStateT::Missile => self.missile,
+                AiStateT::Pain => self.pain,
+                AiStateT::Death => self.death,
+            }
+        }
+    }
+
+// Componente de las entidades que piensan
+#[derive(Clone)]
+pub struct MonsterT {
+    pub info: &'static MonsterInfoT,
+    pub state: AiStateT,
+    pub step: usize,           // Cuadro dentro del estado
+    pub tics: i32,             // Tics que le quedan al cuadro (-1 = para siempre)
+    pub health: i32,
+    pub move_dir: Option<usize>, // Una de las 8 direcciones
+    pub move_count: i32,       // Pasos antes de elegir otra direccion
+    pub reaction_time: i32,
+    pub is_alerted: bool,      // Oyo o recibio un disparo
+    pub just_attacked: bool,
+}
+    impl MonsterT {
+        pub fn frame(&self) -> &'static FrameT {
+            &self.info.frames(self.state)[self.step]
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+// Componente de IA para un sprite de monstruo (None si no es uno)
+pub fn spawn(sprite: &str) -> Option<MonsterT> {
+    let info = MONSTERS.iter().find(|m| m.sprite == sprite)?;
+    Some(MonsterT {
+        info,
+        state: AiStateT::Idle,
+        step: 0,
+        tics: info.idle[0].tics,
+        health: info.health,
+        move_dir: None,
+        move_count: 0,
+        reaction_time: REACTION_TIME,
+        is_alerted: false,
+        just_attacked: false,
+    })
+}
+
+fn set_state(monster: &mut MonsterT, state: AiStateT) {
+    monster.state = state;
+    monster.step = 0;
+    monster.tics = monster.frame().tics;
+}
+
+// Cuadro siguiente; al terminar un estado se pasa al que corresponde
+fn next_frame(monster: &mut MonsterT) {
+    if monster.step + 1 < monster.info.frames(monster.state).len() {
+        monster.step += 1;
+        monster.tics = monster.frame().tics;
+        return;
+    }
+    let next = match monster.state {
+        AiStateT::Idle => AiStateT::Idle,
+        AiStateT::Death => return,
+        _ => AiStateT::Chase,
+    };
+    set_state(monster, next);
+}
+
+fn angle_to(from: &Vec2T, to: &Vec2T) -> f64 {
+    (to.y - from.y).atan2(to.x - from.x)
+}
+
+pub fn roll(state: &mut GameStateT, dice: DiceT) -> i32 {
+    state.rng.range(1, dice.sides + 1) * dice.mult
+}
+
+// Linea de vision desde los ojos del monstruo hasta el cuerpo del jugador
+fn sees_player(state: &GameStateT, entity: &EntityT, player: &PlayerT) -> bool {
+    if player::is_dead(player) {
+        return false;
+    }
+    let eye_z = entity.thing.z + entity.height * 0.75;
+    let feet_z = player.z - player::EYE_HEIGHT;
+    collision::check_sight(&state.sectors, &entity.thing.position, eye_z, &player.position, feet_z, feet_z + collision::PLAYER_HEIGHT)
+}
+
+// Mientras duerme solo ve lo que tiene adelante (o muy cerca)
+fn looks_at_player(state: &GameStateT, entity: &EntityT, player: &PlayerT) -> bool {
+    let offset = (angle_to(&entity.thing.position, &player.position) - entity.thing.angle + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0;
+    let is_in_front = offset.abs() <= FRAC_PI_2 || collision::distance(&entity.thing.position, &player.position) <= MELEE_RANGE;
+    is_in_front && sees_player(state, entity, player)
+}
+
+fn in_melee_range(state: &GameStateT, entity: &EntityT, player: &PlayerT) -> bool {
+    collision::distance(&entity.thing.position, &player.position) < MELEE_RANGE - 20.0 + collision::PLAYER_RADIUS
+        && sees_player(state, entity, player)
+}
+
+// Como P_CheckMissileRange: cuanto mas lejos, menos probable que dispare
+fn wants_to_shoot(state: &mut GameStateT, monster: &MonsterT, sees: bool, dist: f64) -> bool {
+    if monster.reaction_time > 0 || !sees {
+        return false;
+    }
+    let mut dist = dist - 64.0;
+    if monster.info.melee.is_empty() {
+        dist -= 128.0; // Los que solo disparan lo hacen mas seguido
+    }
+    dist < MISSILE_RANGE && state.rng.range(0, 256) as f64 >= dist.min(200.0)
+}
+
+// Un paso en una direccion con la misma colision que el jugador; no baja escalones altos
+fn try_move(state: &mut GameStateT, player: &PlayerT, id: EntityIdT, monster: &MonsterT, dir: usize) -> bool {
+    let Some(entity) = state.entities.get(id) else { return false; };
+    let angle = dir as f64 * FRAC_PI_4;
+    let from = entity.thing.position.clone();
+    let to = Vec2T::new(from.x + angle.cos() * monster.info.speed, from.y + angle.sin() * monster.info.speed);
+    let body = BodyT { radius: entity.radius, height: entity.height, feet_z: entity.thing.z, ignore: Some(id) };
+    if !collision::check_position(&state.sectors, &state.entities, &from, &to, &body) {
+        // Como en Doom los monstruos abren las puertas chocandolas
+        for sector in movers::pushed_portals(&state.sectors, &from, &to, body.radius) {
+            movers::activate(&mut state.sectors, sector, TriggerT::Monster);
+        }
+        return false;
+    }
+    let Some(sector) = entities::find_sector(&state.sectors, to.x, to.y) else { return false; };
+    let floor = state.sectors[sector].floor_z();
+    if body.feet_z - floor > collision::STEP_HEIGHT {
+        return false;
+    }
+    let limit = body.radius + collision::PLAYER_RADIUS;
+    let player_dist = collision::distance(&to, &player.position);
+    if player_dist < limit && player_dist <= collision::distance(&from, &player.position) {
+        return false;
+    }
+
+    let Some(entity) = state.entities.get_mut(id) else { return false; };
+    entity.thing.position = to;
+    entity.thing.z = floor;
+    entity.thing.angle = angle;
+    state.entities.relink(id, sector);
+    true
+}
+
+// Como P_NewChaseDir: prueba la direccion hacia el jugador, las vecinas y al final cualquiera
+fn new_chase_dir(state: &mut GameStateT, player: &PlayerT, id: EntityIdT, monster: &mut MonsterT) {
+    let Some(entity) = state.entities.get(id) else { return; };
+    let target = angle_to(&entity.thing.position, &player.position).rem_euclid(TAU);
+    let best = (target / FRAC_PI_4).round() as i32;
+    // Hacia que lado se prueba primero lo elige el azar
+    let turn = if state.rng.chance(0.5) { 1 } else { -1 };
+    let mut candidates: Vec<usize> = [0, turn, -turn, 2 * turn, -2 * turn].iter()
+        .map(|offset| (best + offset).rem_euclid(NUM_DIRS as i32) as usize).collect();
+    candidates.extend(monster.move_dir);
+    let start = state.rng.range(0, NUM_DIRS as i32) as usize;
+    candidates.extend((0..NUM_DIRS).map(|i| (start + i) % NUM_DIRS));
+    // Darse vuelta es lo ultimo que se intenta
+    let reverse = monster.move_dir.map(|dir| (dir + NUM_DIRS / 2) % NUM_DIRS);
+    candidates.retain(|&dir| Some(dir) != reverse);
+    candidates.extend(reverse);
+
+    for dir in candidates {
+        if try_move(state, player, id, monster, dir) {
+            monster.move_dir = Some(dir);
+            monster.move_count = state.rng.range(0, 16);
+            return;
+        }
+    }
+    monster.move_dir = None;
+}
+
+fn face_player(state: &mut GameStateT, id: EntityIdT, player: &PlayerT) {
+    if let Some(entity) = state.entities.get_mut(id) {
+        entity.thing.angle = angle_to(&entity.thing.position, &player.position);
+    }
+}
+
+fn chase(state: &mut GameStateT, player: &PlayerT, id: EntityIdT, monster: &mut MonsterT) {
+    let Some(entity) = state.entities.get(id) else { return; };
+    // Sin a quien perseguir vuelve a quedarse quieto (como cuando muere su objetivo en Doom)
+    if player::is_dead(player) {
+        monster.is_alerted = false;
+        set_state(monster, AiStateT::Idle);
+        return;
+    }
+    if monster.reaction_time > 0 {
+        monster.reaction_time -= 1;
+    }
+    if monster.just_attacked {
+        monster.just_attacked = false;
+        new_chase_dir(state, player, id, monster);
+        return;
+    }
+    if !monster.info.melee.is_empty() && in_melee_range(state, entity, player) {
+        set_state(monster, AiStateT::Melee);
+        return;
+    }
+    let sees = sees_player(state, entity, player);
+    let dist = collision::distance(&entity.thing.position, &player.position);
+    if !monster.info.missile.is_empty() && monster.move_count == 0 && wants_to_shoot(state, monster, sees, dist) {
+        set_state(monster, AiStateT::Missile);
+        monster.just_attacked = true;
+        return;
+    }
+    monster.move_count -= 1;
+    let moved = monster.move_dir.is_some_and(|dir| try_move(state, player, id, monster, dir));
+    if monster.move_count < 0 || !moved {
+        new_chase_dir(state, player, id, monster);
+    }
+}
+
+fn act(state: &mut GameStateT, player: &mut PlayerT, id: EntityIdT, monster: &mut MonsterT, action: ActionT) {
+    let Some(entity) = state.entities.get(id) else { return; };
+    match action {
+        ActionT::Look => {
+            if monster.is_alerted || looks_at_player(state, entity, player) {
+                set_state(monster, AiStateT::See);
+            }
+        }
+        ActionT::Chase => chase(state, player, id, monster),
+        ActionT::FaceTarget => face_player(state, id, player),
+        ActionT::Melee(dice) => {
+            let hits = in_melee_range(state, entity, player);
+            face_player(state, id, player);
+            if hits {
+                let amount = roll(state, dice);
+                player::damage(player, amount);
+            }
+        }
+        ActionT::Hitscan(shots, dice) => {
+            let dist = collision::distance(&entity.thing.position, &player.position);
+            let can_hit = dist < MISSILE_RANGE && sees_player(state, entity, player);
+            face_player(state, id, player);
+            // Pega si el desvio del disparo queda dentro del ancho del jugador
+            let tolerance = collision::PLAYER_RADIUS.atan2(dist);
+            for _ in 0..shots {
+                let spread = (state.rng.range(0, 256) - state.rng.range(0, 256)) as f64 / 255.0 * SPREAD;
+                let amount = roll(state, dice);
+                if can_hit && spread.abs() < tolerance {
+                    player::damage(player, amount);
+                }
+            }
+        }
+        ActionT::Projectile(info) => {
+            // Apunta al centro del jugador desde la mitad del cuerpo
+            let from = entity.thing.position.clone();
+            let z = entity.thing.z + entity.height * 0.5;
+            let dist = collision::distance(&from, &player.position).max(1.0);
+            let angle = angle_to(&from, &player.position);
+            let slope = (player.z - player::EYE_HEIGHT + collision::PLAYER_HEIGHT * 0.5 - z) / dist;
+            face_player(state, id, player);
+            weapons::spawn_projectile(state, info, &from, z, angle, slope, Some(id));
+        }
+    }
+}
+
+// Ejecuta la accion del cuadro actual; si cambia de estado tambien la del primer cuadro del nuevo
+fn run_frame(state: &mut GameStateT, player: &mut PlayerT, id: EntityIdT, monster: &mut MonsterT) {
+    for _ in 0..MAX_STATE_CHAIN {
+        let before = (monster.state, monster.step);
+        let Some(action) = monster.frame().action else { return; };
+        act(state, player, id, monster, action);
+        if (monster.state, monster.step) == before {
+            return;
+        }
+    }
+}
+
+// Saca el componente de la entidad para poder tocar el resto del mundo y despues lo devuelve
+fn with_monster(state: &mut GameStateT, id: EntityIdT, f: impl FnOnce(&mut GameStateT, &mut MonsterT)) {
+    let Some(mut monster) = state.entities.get_mut(id).and_then(|e| e.monster.take()) else { return; };
+    f(state, &mut monster);
+    if let Some(entity) = state.entities.get_mut(id) {
+        entity.thing.frame = monster.frame().frame;
+        entity.is_solid = monster.health > 0;
+        entity.monster = Some(monster);
+    }
+}
+
+// Avanza un tic todos los monstruos
+pub fn tick(state: &mut GameStateT, player: &mut PlayerT) {
+    let ids: Vec<EntityIdT> = state.entities.iter().filter(|e| e.monster.is_some()).map(|e| e.id).collect();
+    for id in ids {
+        with_monster(state, id, |state, monster| {
+            if monster.tics < 0 {
+                return;
+            }
+            monster.tics -= 1;
+            if monster.tics > 0 {
+                return;
+            }
+            next_frame(monster);
+            run_frame(state, player, id, monster);
+        });
+    }
+}
+
+// Le hace daño a un monstruo: puede sentir dolor o morir, y siempre se despierta
+pub fn damage_monster(state: &mut GameStateT, player: &mut PlayerT, id: EntityIdT, amount: i32) {
+    with_monster(state, id, |state, monster| {
+        if monster.health <= 0 {
+            return;
+        }
+        monster.health -= amount;
+        monster.is_alerted = true;
+        if monster.health <= 0 {
+            state.entities.count_kill(id);
+            set_state(monster, AiStateT::Death);
+        } else if state.rng.range(0, 256) < monster.info.pain_chance {
+            set_state(monster, AiStateT::Pain);
+        } else if monster.state == AiStateT::Idle {
+            set_state(monster, AiStateT::See);
+        } else {
+            return;
+        }
+        run_frame(state, player, id, monster);
+    });
+}
+
+// Sectores a los que llega un ruido: se propaga por los portales abiertos (una puerta cerrada
+// no deja hueco) y se corta en la segunda pared block_sound, como P_RecursiveSound de Doom
+fn propagate_sound(sectors: &[SectorT], origin: usize) -> Vec<bool> {
+    // Menor cantidad de paredes block_sound cruzadas para llegar a cada sector
+    let mut blocks: Vec<Option<u32>> = vec![None; sectors.len()];
+    let mut pending = vec![(origin, 0)];
+    while let Some((sector, crossed)) = pending.pop() {
+        if blocks[sector].is_some_and(|prev| prev <= crossed) {
+            continue;
+        }
+        blocks[sector] = Some(crossed);
+        for wall in &sectors[sector].walls {
+            let Some(next) = wall.neighbor else { continue; };
+            let (floor, ceil) = collision::opening(&sectors[sector], &sectors[next]);
+            let crossed = crossed + wall.blocks_sound as u32;
+            if ceil > floor && crossed < SOUND_BLOCKS {
+                pending.push((next, crossed));
+            }
+        }
+    }
+    blocks.iter().map(Option::is_some).collect()
+}
+
+// Despierta a los monstruos de los sectores a los que llega el ruido; devuelve (sectores, despertados)
+pub fn noise_alert(state: &mut GameStateT, origin: usize) -> (usize, usize) {
+    let reached = propagate_sound(&state.sectors, origin);
+    let heard: Vec<EntityIdT> = reached.iter().enumerate().filter(|&(_, &r)| r)
+        .flat_map(|(sector, _)| state.entities.in_sector(sector))
+        .filter(|e| e.monster.as_ref().is_some_and(|m| m.health > 0 && !m.is_alerted))
+        .map(|e| e.id).collect();
+    for &id in &heard {
+        if let Some(monster) = state.entities.get_mut(id).and_then(|e| e.monster.as_mut()) {
+            monster.is_alerted = true;
+        }
+    }
+    (reached.iter().filter(|&&r| r).count(), heard.len())
+}
+
+pub fn is_alive(entity: &EntityT) -> bool {
+    entity.monster.as_ref().is_some_and(|m| m.health > 0)
+}
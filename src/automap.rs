@@ -0,0 +1,193 @@
+use std::collections::HashSet;
+use crate::{entities::{EntityT, ThingKindT}, game_state::GameStateT, intermission, keyboard::KeystatesT, player::PlayerT, renderer::{SectorT, Screen, WallT}, text::{self, AlignT, TextStyleT}, typedefs::Vec2T};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+// Colores como los del automapa de Doom
+pub const SOLID_WALL_CLR: u32 = 0xFC0000;
+pub const FLOOR_CHANGE_CLR: u32 = 0xBC7838;
+pub const CEIL_CHANGE_CLR: u32 = 0xFCFC00;
+pub const PORTAL_CLR: u32 = 0x606060;
+pub const PLAYER_CLR: u32 = 0xFFFFFF;
+pub const BACKGROUND_CLR: u32 = 0x000000;
+pub const UNSEEN_CLR: u32 = 0x404040; // Paredes todavia no vistas cuando se revela el mapa
+pub const ENEMY_CLR: u32 = 0x00FC00;
+pub const ITEM_CLR: u32 = 0x00A0FC;
+pub const DECORATION_CLR: u32 = 0x808080;
+pub const PROJECTILE_CLR: u32 = 0xFC8000;
+
+pub const MIN_SCALE: f64 = 0.05;
+pub const MAX_SCALE: f64 = 8.0;
+pub const ZOOM_SPEED: f64 = 2.0;  // Se duplica la escala cada medio segundo
+pub const PAN_SPEED: f64 = 400.0; // Pixeles de pantalla por segundo
+pub const ARROW_SIZE: f64 = 16.0; // Largo de la flecha del jugador en unidades del mundo
+pub const STATUS_CLR: u32 = 0xC0C0C0;
+pub const STATUS_SCALE: i32 = 2;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+pub struct AutomapT {
+    pub scale: f64,     // Pixeles por unidad del mundo
+    pub center: Vec2T,  // Punto del mundo en el centro de la pantalla
+    pub follow: bool,   // Seguir al jugador
+    pub overlay: bool,  // Dibujar encima de la vista 3D en vez de reemplazarla
+    pub seen: HashSet<(usize, usize)>, // (sector, pared) dibujadas alguna vez por el renderer
+    pub reveal_all: bool, // Truco: mostrar todo el mapa
+}
+    impl AutomapT {
+        pub fn new() -> Self {
+            AutomapT {
+                scale: 0.5,
+                center: Vec2T::new(0.0, 0.0),
+                follow: true,
+                overlay: false,
+                seen: HashSet::new(),
+                reveal_all: false,
+            }
+        }
+
+        pub fn mark_seen(&mut self, walls: &[(usize, usize)]) {
+            self.seen.extend(walls.iter().copied());
+        }
+
+        // Un portal cuenta como visto si se dibujo desde cualquiera de sus dos lados
+        fn is_seen(&self, sectors: &[SectorT], sector: usize, wall: usize) -> bool {
+            if self.seen.contains(&(sector, wall)) {
+                return true;
+            }
+            let w = &sectors[sector].walls[wall];
+            let Some(n) = w.neighbor else { return false; };
+            sectors[n].walls.iter().enumerate().any(|(i, other)| {
+                other.neighbor == Some(sector) && other.a == w.b && other.b == w.a && self.seen.contains(&(n, i))
+            })
+        }
+
+        // Mundo -> pantalla (la y del mundo crece hacia arriba)
+        fn to_screen(&self, p: &Vec2T, screen: &Screen) -> (i32, i32) {
+            let sx = screen.width as f64 / 2.0 + (p.x - self.center.x) * self.scale;
+            let sy = screen.height as f64 / 2.0 - (p.y - self.center.y) * self.scale;
+            (sx.round() as i32, sy.round() as i32)
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+
+// Zoom y desplazamiento con las teclas mantenidas
+pub fn process_input(automap: &mut AutomapT, keystates: &KeystatesT, player: &PlayerT, delta_time: f64) {
+    if keystates.map_zoom_in {
+        automap.scale *= ZOOM_SPEED.powf(delta_time * 2.0);
+    } else if keystates.map_zoom_out {
+        automap.scale /= ZOOM_SPEED.powf(delta_time * 2.0);
+    }
+    automap.scale = automap.scale.clamp(MIN_SCALE, MAX_SCALE);
+
+    if automap.follow {
+        automap.center = player.position.clone();
+        return;
+    }
+    let step = PAN_SPEED * delta_time / automap.scale;
+    if keystates.map_pan_left { automap.center.x -= step; }
+    if keystates.map_pan_right { automap.center.x += step; }
+    if keystates.map_pan_up { automap.center.y += step; }
+    if keystates.map_pan_down { automap.center.y -= step; }
+}
+
+// Color de una pared segun su tipo
+fn wall_color(sector: &SectorT, wall: &WallT, sectors: &[SectorT]) -> u32 {
+    match wall.neighbor {
+        None => SOLID_WALL_CLR,
+        Some(n) if sectors[n].elevation != sector.elevation => FLOOR_CHANGE_CLR,
+        Some(n) if sectors[n].ceil_z() != sector.ceil_z() => CEIL_CHANGE_CLR,
+        Some(_) => PORTAL_CLR,
+    }
+}
+
+fn draw_player_arrow(screen: &mut Screen, automap: &AutomapT, player: &PlayerT) {
+    // Flecha en coordenadas locales (punta hacia +x), despues se rota con el angulo del jugador
+    let shape: [((f64, f64), (f64, f64)); 5] = [
+        ((-1.0, 0.0), (1.0, 0.0)),
+        ((1.0, 0.0), (0.4, 0.45)),
+        ((1.0, 0.0), (0.4, -0.45)),
+        ((-1.0, 0.0), (-1.3, 0.35)),
+        ((-1.0, 0.0), (-1.3, -0.35)),
+    ];
+    let (sin_a, cos_a) = player.dir_angle.sin_cos();
+    let to_world = |(x, y): (f64, f64)| {
+        let (x, y) = (x * ARROW_SIZE, y * ARROW_SIZE);
+        Vec2T::new(player.position.x + x * cos_a - y * sin_a, player.position.y + x * sin_a + y * cos_a)
+    };
+    for (a, b) in shape {
+        let (x0, y0) = automap.to_screen(&to_world(a), screen);
+        let (x1, y1) = automap.to_screen(&to_world(b), screen);
+        screen.line(x0, y0, x1, y1, PLAYER_CLR);
+    }
+}
+
+// Triangulo del tamaño del thing apuntando hacia donde mira
+fn draw_thing(screen: &mut Screen, automap: &AutomapT, entity: &EntityT) {
+    let color = match entity.kind {
+        ThingKindT::Enemy => ENEMY_CLR,
+        ThingKindT::Item => ITEM_CLR,
+        ThingKindT::Decoration => DECORATION_CLR,
+        ThingKindT::Projectile => PROJECTILE_CLR,
+    };
+    let (pos, r) = (&entity.thing.position, entity.radius);
+    let corner = |angle: f64| Vec2T::new(pos.x + r * angle.cos(), pos.y + r * angle.sin());
+    let a = entity.thing.angle;
+    let points = [corner(a), corner(a + 2.5), corner(a - 2.5)];
+    for i in 0..points.len() {
+        let (x0, y0) = automap.to_screen(&points[i], screen);
+        let (x1, y1) = automap.to_screen(&points[(i + 1) % points.len()], screen);
+        screen.line(x0, y0, x1, y1, color);
+    }
+}
+
+// Dibuja el automapa en el framebuffer (solo o encima de la vista 3D)
+pub fn draw(screen: &mut Screen, game_state: &GameStateT, player: &PlayerT) {
+    let automap = &game_state.automap;
+    if !automap.overlay {
+        screen.fill(BACKGROUND_CLR);
+    }
+
+    for (index, sector) in game_state.sectors.iter().enumerate() {
+        for (wall_index, wall) in sector.walls.iter().enumerate() {
+            // Cada portal aparece en los dos sectores: se dibuja una sola vez
+            if wall.neighbor.is_some_and(|n| n < index) {
+                continue;
+            }
+            let color = if automap.is_seen(&game_state.sectors, index, wall_index) {
+                wall_color(sector, wall, &game_state.sectors)
+            } else if automap.reveal_all {
+                UNSEEN_CLR
+            } else {
+                continue;
+            };
+            let (x0, y0) = automap.to_screen(&wall.a, screen);
+            let (x1, y1) = automap.to_screen(&wall.b, screen);
+            screen.line(x0, y0, x1, y1, color);
+        }
+    }
+    // Con el mapa revelado tambien se ven los things
+    if automap.reveal_all {
+        for entity in game_state.entities.iter() {
+            draw_thing(screen, automap, entity);
+        }
+    }
+    draw_player_arrow(screen, automap, player);
+
+    // Nombre del mapa y contadores del nivel abajo al centro, con el estado de los modos debajo
+    let font = &game_state.font;
+    let line_h = font.line_height(STATUS_SCALE);
+    let style = TextStyleT::new(STATUS_CLR, STATUS_SCALE).aligned(AlignT::Center);
+    let stats = format!("{}  {}", intermission::map_name(&game_state.map_path), intermission::summary(game_state));
+    text::draw_text(screen, font, screen.width as i32 / 2, screen.height as i32 - line_h * 2 - 4, &stats, &style);
+    let mut status = Vec::new();
+    if !automap.follow {
+        status.push("FOLLOW OFF");
+    }
+    if automap.reveal_all {
+        status.push("FULL MAP");
+    }
+    if !status.is_empty() {
+        text::draw_text(screen, font, screen.width as i32 / 2, screen.height as i32 - line_h - 4, &status.join("  "), &style);
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::Path;
+use crate::{game_state::GameStateT, typedefs::Vec2T, wad::WadT};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const MAPS_DIR: &str = "maps";
+pub const MAP_EXTENSION: &str = "map";
+
+// Opciones sin valor
+const FLAGS: [&str; 6] = ["--help", "--version", "--uncapped", "--fullscreen", "--windowed", "--headless"];
+
+pub const USAGE: &str = "\
+Uso: doom-rust [opciones]
+
+Mapa y recursos:
+  -m, --map <archivo|nombre>   Mapa a cargar (un nombre busca maps/<nombre>.map)
+  -e, --episode <archivo>      Orden de los mapas, uno por linea (por defecto maps/episode.txt
+                               o los niveles del WAD que esten en maps/)
+  -w, --wad <archivo>          WAD con paleta, fuentes y sprites (por defecto assets/doom1.wad)
+      --start <x,y[,angulo]>   Empieza en otra posicion (angulo en grados)
+
+Video:
+  -r, --resolution <AxB>       Resolucion de la ventana, por ejemplo 1280x720
+      --fps <n>                Limite de FPS
+      --uncapped               Sin limite de FPS
+      --fullscreen             Pantalla completa
+      --windowed               En ventana
+
+Demos y diagnostico:
+      --record <archivo>       Graba la entrada en una demo (se guarda al salir)
+      --play <archivo>         Reproduce una demo
+      --headless               Sin ventana (necesita --play o --frames)
+      --frames <n>             Sale despues de n frames
+      --screenshot <archivo>   Guarda una captura BMP del ultimo frame y sale
+
+  -h, --help                   Muestra esta ayuda
+  -V, --version                Muestra la version";
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Opciones de la linea de comandos (None = lo que diga la configuracion)
+pub struct ArgsT {
+    pub show_help: bool,
+    pub show_version: bool,
+    pub map: Option<String>,
+    pub episode: Option<String>,
+    pub wad: Option<String>,
+    pub start: Option<(Vec2T, Option<f64>)>,
+    pub resolution: Option<(u32, u32)>,
+    pub fps: Option<f64>,
+    pub uncapped: bool,
+    pub fullscreen: Option<bool>,
+    pub record: Option<String>,
+    pub play: Option<String>,
+    pub headless: bool,
+    pub frames: Option<u64>,
+    pub screenshot: Option<String>,
+}
+    impl ArgsT {
+        pub fn new() -> Self {
+            ArgsT {
+                show_help: false,
+                show_version: false,
+                map: None,
+                episode: None,
+                wad: None,
+                start: None,
+                resolution: None,
+                fps: None,
+                uncapped: false,
+                fullscreen: None,
+                record: None,
+                play: None,
+                headless: false,
+                frames: None,
+                screenshot: None,
+            }
+        }
+
+        // Sin menu de inicio: se entra directo al mapa
+        pub fn skips_title(&self) -> bool {
+            self.map.is_some() || self.start.is_some() || self.record.is_some() || self.play.is_some()
+                || self.headless || self.screenshot.is_some()
+        }
+
+        // Frames a correr antes de salir (una captura sin demo sale al primer frame)
+        pub fn frame_limit(&self) -> Option<u64> {
+            match self.frames {
+                Some(frames) => Some(frames),
+                None if self.screenshot.is_some() && self.play.is_none() => Some(1),
+                None => None,
+            }
+        }
+
+        // Los valores de la linea de comandos pisan los guardados solo en el estado (state.settings no cambia)
+        pub fn apply(&self, state: &mut GameStateT) {
+            if let Some((w, h)) = self.resolution {
+                state.scrn_w = w;
+                state.scrn_h = h;
+            }
+            if let Some(fps) = self.fps {
+                state.target_fps = fps;
+                state.target_frame_time = 1.0 / fps;
+                state.delta_time = 1.0 / fps;
+                state.is_fps_capped = true;
+            }
+            if self.uncapped {
+                state.is_fps_capped = false;
+            }
+            if let Some(fullscreen) = self.fullscreen {
+                state.is_fullscreen = fullscreen;
+            }
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("{}: numero invalido '{}'", option, value))
+}
+
+fn parse_resolution(option: &str, value: &str) -> Result<(u32, u32), String> {
+    let (w, h) = value.split_once(['x', 'X']).ok_or(format!("{}: se esperaba ANCHOxALTO, no '{}'", option, value))?;
+    let (w, h) = (parse_number::<u32>(option, w)?, parse_number::<u32>(option, h)?);
+    if !(160..=7680).contains(&w) || !(120..=4320).contains(&h) {
+        return Err(format!("{}: resolucion fuera de rango ({}x{})", option, w, h));
+    }
+    Ok((w, h))
+}
+
+fn parse_start(option: &str, value: &str) -> Result<(Vec2T, Option<f64>), String> {
+    let parts = value.split(',').map(|v| parse_number::<f64>(option, v.trim())).collect::<Result<Vec<f64>, String>>()?;
+    match parts[..] {
+        [x, y] => Ok((Vec2T::new(x, y), None)),
+        [x, y, angle] => Ok((Vec2T::new(x, y), Some(angle.to_radians()))),
+        _ => Err(format!("{}: se esperaba x,y o x,y,angulo", option)),
+    }
+}
+
+fn set_once<T>(slot: &mut Option<T>, option: &str, value: T) -> Result<(), String> {
+    if slot.is_some() {
+        return Err(format!("{} repetida", option));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+// Lee los argumentos (sin el nombre del programa); acepta '--opcion valor' y '--opcion=valor'
+pub fn parse<I: IntoIterator<Item = String>>(raw_args: I) -> Result<ArgsT, String> {
+    let mut args = ArgsT::new();
+    let mut raw_args = raw_args.into_iter();
+    while let Some(arg) = raw_args.next() {
+        let (option, inline_value) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => (option.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        if !option.starts_with('-') {
+            return Err(format!("argumento inesperado '{}'", option));
+        }
+        if inline_value.is_some() && FLAGS.contains(&option.as_str()) {
+            return Err(format!("{} no lleva valor", option));
+        }
+        let mut value = || inline_value.clone().or_else(|| raw_args.next()).ok_or(format!("{} necesita un valor", option));
+
+        match option.as_str() {
+            "-h" | "--help" => args.show_help = true,
+            "-V" | "--version" => args.show_version = true,
+            "-m" | "--map" => set_once(&mut args.map, &option, value()?)?,
+            "-e" | "--episode" => set_once(&mut args.episode, &option, value()?)?,
+            "-w" | "--wad" => set_once(&mut args.wad, &option, value()?)?,
+            "--start" => {
+                let start = parse_start(&option, &value()?)?;
+                set_once(&mut args.start, &option, start)?;
+            }
+            "-r" | "--resolution" => {
+                let resolution = parse_resolution(&option, &value()?)?;
+                set_once(&mut args.resolution, &option, resolution)?;
+            }
+            "--fps" => {
+                let fps = parse_number::<f64>(&option, &value()?)?;
+                if !(10.0..=1000.0).contains(&fps) {
+                    return Err(format!("{}: tiene que estar entre 10 y 1000", option));
+                }
+                set_once(&mut args.fps, &option, fps)?;
+            }
+            "--uncapped" => args.uncapped = true,
+            "--fullscreen" => set_once(&mut args.fullscreen, "--fullscreen/--windowed", true)?,
+            "--windowed" => set_once(&mut args.fullscreen, "--fullscreen/--windowed", false)?,
+            "--record" => set_once(&mut args.record, &option, value()?)?,
+            "--play" => set_once(&mut args.play, &option, value()?)?,
+            "--headless" => args.headless = true,
+            "--frames" => {
+                let frames = parse_number::<u64>(&option, &value()?)?;
+                if frames == 0 {
+                    return Err(format!("{}: tiene que ser mayor que 0", option));
+                }
+                set_once(&mut args.frames, &option, frames)?;
+            }
+            "--screenshot" => set_once(&mut args.screenshot, &option, value()?)?,
+            _ => return Err(format!("opcion desconocida '{}'", option)),
+        }
+    }
+    validate(&args)?;
+    Ok(args)
+}
+
+// Combinaciones que no tienen sentido
+fn validate(args: &ArgsT) -> Result<(), String> {
+    if args.record.is_some() && args.play.is_some() {
+        return Err("--record y --play no se pueden usar juntas".to_string());
+    }
+    if args.play.is_some() && (args.map.is_some() || args.start.is_some()) {
+        return Err("--play usa el mapa y el inicio de la demo: sacar --map/--start".to_string());
+    }
+    if args.fps.is_some() && args.uncapped {
+        return Err("--fps y --uncapped no se pueden usar juntas".to_string());
+    }
+    if args.headless {
+        if args.record.is_some() {
+            return Err("--headless no tiene entrada para grabar con --record".to_string());
+        }
+        if args.fullscreen.is_some() {
+            return Err("--headless no abre ventana: sacar --fullscreen/--windowed".to_string());
+        }
+        if args.play.is_none() && args.frames.is_none() {
+            return Err("--headless necesita --play o --frames para terminar".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Un nombre de mapa se busca en maps/<nombre>.map
+pub fn resolve_map(name: &str, wad: Option<&WadT>) -> Result<String, String> {
+    if Path::new(name).is_file() {
+        return Ok(name.to_string());
+    }
+    let candidate = Path::new(MAPS_DIR).join(format!("{}.{}", name, MAP_EXTENSION));
+    if candidate.is_file() {
+        return Ok(candidate.to_string_lossy().into_owned());
+    }
+    // Los niveles de los WAD (E1M1, MAP01...) todavia no se pueden cargar
+    if wad.is_some_and(|wad| wad.find_lump(&name.to_uppercase()).is_some()) {
+        return Err(format!("'{}' es un mapa del WAD, pero el motor solo carga mapas .{}", name, MAP_EXTENSION));
+    }
+    Err(format!("no se encontro el mapa '{}' (ni {})", name, candidate.display()))
+}
+
+pub fn version() -> String {
+    format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
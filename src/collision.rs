@@ -0,0 +1,261 @@
+use crate::{entities::{self, EntitiesT, EntityIdT}, renderer::{SectorT, WallT}, typedefs::Vec2T};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const PLAYER_RADIUS: f64 = 16.0;
+pub const PLAYER_HEIGHT: f64 = 56.0;
+pub const STEP_HEIGHT: f64 = 24.0; // Escalon mas alto que se puede subir caminando
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Lo que ocupa algo que se mueve: un cilindro apoyado en feet_z
+pub struct BodyT {
+    pub radius: f64,
+    pub height: f64,
+    pub feet_z: f64,
+    pub ignore: Option<EntityIdT>, // La propia entidad (no choca consigo misma)
+}
+
+// Disparo o golpe: sale de origin a la altura z con un angulo y una pendiente vertical
+pub struct RayT {
+    pub origin: Vec2T,
+    pub z: f64,
+    pub angle: f64,
+    pub slope: f64,
+    pub range: f64,
+}
+
+// Lo que freno un rayo: un thing o, si no toco ninguno antes, la pared (sector, indice) donde termino
+pub struct TraceHitT {
+    pub thing: Option<EntityIdT>,
+    pub wall: Option<(usize, usize)>,
+}
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+pub fn distance(a: &Vec2T, b: &Vec2T) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn distance_to_segment(p: &Vec2T, a: &Vec2T, b: &Vec2T) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 > 0.0 { (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    distance(p, &Vec2T::new(a.x + dx * t, a.y + dy * t))
+}
+
+// Fraccion de p->q donde cruza el segmento a-b (si lo cruza)
+pub fn segment_crossing(p: &Vec2T, q: &Vec2T, a: &Vec2T, b: &Vec2T) -> Option<f64> {
+    let (rx, ry) = (q.x - p.x, q.y - p.y);
+    let (sx, sy) = (b.x - a.x, b.y - a.y);
+    let denom = rx * sy - ry * sx;
+    if denom.abs() < 1e-9 {
+        return None;
+    }
+    let (wx, wy) = (a.x - p.x, a.y - p.y);
+    let t = (wx * sy - wy * sx) / denom;
+    let u = (wx * ry - wy * rx) / denom;
+    ((0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&t)).then_some(t)
+}
+
+pub fn distance_to_wall(p: &Vec2T, wall: &WallT) -> f64 {
+    distance_to_segment(p, &wall.a, &wall.b)
+}
+
+// Si un circulo en pos esta dentro del sector index o pisa alguna de sus paredes
+pub fn touches_sector(sectors: &[SectorT], index: usize, pos: &Vec2T, radius: f64) -> bool {
+    sectors[index].contains(pos.x, pos.y) || sectors[index].walls.iter().any(|w| distance_to_wall(pos, w) < radius)
+}
+
+// Los sectores de origins y sus vecinos por portales, sin repetir (donde buscar things cercanos)
+fn sectors_around(sectors: &[SectorT], origins: &[usize]) -> Vec<usize> {
+    let mut near: Vec<usize> = origins.iter().flat_map(|&s| sectors[s].walls.iter().filter_map(|w| w.neighbor)).collect();
+    near.extend(origins);
+    near.sort_unstable();
+    near.dedup();
+    near
+}
+
+// El sector index y sus vecinos por portales
+pub fn nearby_sectors(sectors: &[SectorT], index: usize) -> Vec<usize> {
+    sectors_around(sectors, &[index])
+}
+
+// Hueco vertical de un portal: el piso mas alto y el techo mas bajo de los dos lados
+pub fn opening(a: &SectorT, b: &SectorT) -> (f64, f64) {
+    (a.floor_z().max(b.floor_z()), a.ceil_z().min(b.ceil_z()))
+}
+
+// Si el cuerpo entra en pos sin chocar con paredes, escalones o techos
+pub fn check_walls(sectors: &[SectorT], pos: &Vec2T, body: &BodyT) -> bool {
+    let Some(sector) = entities::find_sector(sectors, pos.x, pos.y) else { return false; };
+    let floor = sectors[sector].floor_z();
+    if floor - body.feet_z > STEP_HEIGHT || sectors[sector].ceil_z() - floor.max(body.feet_z) < body.height {
+        return false;
+    }
+    // Solo las paredes del sector y de sus vecinos (las demas quedan lejos del cuerpo)
+    for index in nearby_sectors(sectors, sector) {
+        for wall in &sectors[index].walls {
+            if distance_to_segment(pos, &wall.a, &wall.b) >= body.radius {
+                continue;
+            }
+            let Some(n) = wall.neighbor else { return false; };
+            let (floor, ceil) = opening(&sectors[index], &sectors[n]);
+            if floor - body.feet_z > STEP_HEIGHT || ceil - floor.max(body.feet_z) < body.height {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Thing solido del sector de pos (o de sus vecinos) con el que choca el cuerpo
+pub fn blocking_thing(sectors: &[SectorT], entities: &EntitiesT, from: &Vec2T, pos: &Vec2T, body: &BodyT) -> Option<EntityIdT> {
+    let sector = entities::find_sector(sectors, pos.x, pos.y)?;
+    let near = nearby_sectors(sectors, sector);
+    let blocker = near.iter().flat_map(|&s| entities.in_sector(s)).find(|entity| {
+        if !entity.is_solid || Some(entity.id) == body.ignore {
+            return false;
+        }
+        // Uno encima del otro no chocan
+        if body.feet_z >= entity.thing.z + entity.height || body.feet_z + body.height <= entity.thing.z {
+            return false;
+        }
+        let limit = body.radius + entity.radius;
+        let new_dist = distance(pos, &entity.thing.position);
+        // Si ya estaban encimados se deja que se alejen
+        new_dist < limit && new_dist <= distance(from, &entity.thing.position)
+    });
+    blocker.map(|entity| entity.id)
+}
+
+// Si el cuerpo entra en pos viniendo de from (paredes, escalones, techos y things solidos)
+pub fn check_position(sectors: &[SectorT], entities: &EntitiesT, from: &Vec2T, pos: &Vec2T, body: &BodyT) -> bool {
+    check_walls(sectors, pos, body) && blocking_thing(sectors, entities, from, pos, body).is_none()
+}
+
+// Mueve un cuerpo deslizandose por lo que lo bloquea; devuelve la posicion final
+pub fn slide_move(sectors: &[SectorT], entities: &EntitiesT, from: &Vec2T, delta: &Vec2T, body: &BodyT) -> Vec2T {
+    // Pasos cortos para no atravesar paredes finas en un frame largo
+    let steps = ((delta.x.hypot(delta.y) / (body.radius * 0.5)).ceil() as usize).max(1);
+    let (dx, dy) = (delta.x / steps as f64, delta.y / steps as f64);
+    let mut pos = from.clone();
+    for _ in 0..steps {
+        let candidates = [Vec2T::new(pos.x + dx, pos.y + dy), Vec2T::new(pos.x + dx, pos.y), Vec2T::new(pos.x, pos.y + dy)];
+        match candidates.into_iter().find(|c| check_position(sectors, entities, &pos, c, body)) {
+            Some(next) => pos = next,
+            None => break,
+        }
+    }
+    pos
+}
+
+// Linea de vision entre dos puntos atravesando portales: como en Doom se achica una ventana
+// de pendientes (desde el ojo hasta la base y la cima del objetivo) con cada hueco que cruza
+pub fn check_sight(sectors: &[SectorT], from: &Vec2T, eye_z: f64, to: &Vec2T, bottom_z: f64, top_z: f64) -> bool {
+    let (Some(mut sector), Some(target)) = (entities::find_sector(sectors, from.x, from.y), entities::find_sector(sectors, to.x, to.y)) else {
+        return false;
+    };
+    let dist = distance(from, to);
+    if dist < 1e-6 {
+        return true;
+    }
+    let mut top_slope = (top_z - eye_z) / dist;
+    let mut bottom_slope = (bottom_z - eye_z) / dist;
+    let mut last_t = 0.0;
+    for _ in 0..=sectors.len() {
+        if sector == target {
+            return bottom_slope < top_slope;
+        }
+        // Primera pared del sector que cruza la linea despues del ultimo cruce
+        let crossing = sectors[sector].walls.iter()
+            .filter_map(|wall| segment_crossing(from, to, &wall.a, &wall.b).map(|t| (t, wall.neighbor)))
+            .filter(|(t, _)| *t > last_t + 1e-9)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((t, Some(next))) = crossing else { return false; };
+        let (floor, ceil) = opening(&sectors[sector], &sectors[next]);
+        let d = (t * dist).max(1e-6);
+        top_slope = top_slope.min((ceil - eye_z) / d);
+        bottom_slope = bottom_slope.max((floor - eye_z) / d);
+        if bottom_slope >= top_slope {
+            return false;
+        }
+        sector = next;
+        last_t = t;
+    }
+    false
+}
+
+// Traza un rayo que se frena en la primera pared, piso o techo; devuelve el thing solido mas
+// cercano que toca antes de frenar (o la pared en la que freno)
+pub fn trace(sectors: &[SectorT], entities: &EntitiesT, ray: &RayT, ignore: Option<EntityIdT>) -> TraceHitT {
+    let (from, z, slope, range) = (&ray.origin, ray.z, ray.slope, ray.range);
+    let (dx, dy) = (ray.angle.cos(), ray.angle.sin());
+    let to = Vec2T::new(from.x + dx * range, from.y + dy * range);
+    let Some(mut sector) = entities::find_sector(sectors, from.x, from.y) else {
+        return TraceHitT { thing: None, wall: None };
+    };
+
+    // Recorre los sectores que cruza hasta chocar con algo
+    let mut visited = vec![sector];
+    let mut last_t = 0.0;
+    let mut stop_t = 1.0;
+    let mut stop_wall = None;
+    for _ in 0..=sectors.len() {
+        let (floor, ceil) = (sectors[sector].floor_z(), sectors[sector].ceil_z());
+        let crossing = sectors[sector].walls.iter().enumerate()
+            .filter_map(|(index, wall)| segment_crossing(from, &to, &wall.a, &wall.b).map(|t| (t, index, wall.neighbor)))
+            .filter(|(t, _, _)| *t > last_t + 1e-9)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        // Piso o techo del sector antes de llegar a la pared
+        let exit_t = crossing.map_or(1.0, |(t, _, _)| t);
+        let plane_t = match slope {
+            s if s < 0.0 => (floor - z) / (s * range),
+            s if s > 0.0 => (ceil - z) / (s * range),
+            _ => f64::INFINITY,
+        };
+        if plane_t < exit_t {
+            stop_t = plane_t.max(last_t);
+            break;
+        }
+        let Some((t, index, neighbor)) = crossing else { break; };
+        let Some(next) = neighbor else {
+            stop_t = t;
+            stop_wall = Some((sector, index));
+            break;
+        };
+        let (open_floor, open_ceil) = opening(&sectors[sector], &sectors[next]);
+        let z_at = z + slope * range * t;
+        if z_at < open_floor || z_at > open_ceil {
+            stop_t = t;
+            stop_wall = Some((sector, index));
+            break;
+        }
+        sector = next;
+        visited.push(next);
+        last_t = t;
+    }
+
+    // El thing mas cercano que el rayo toca antes de frenar
+    let near = sectors_around(sectors, &visited);
+    let mut best: Option<(f64, EntityIdT)> = None;
+    for entity in near.iter().flat_map(|&s| entities.in_sector(s)) {
+        if !entity.is_solid || Some(entity.id) == ignore {
+            continue;
+        }
+        let (cx, cy) = (entity.thing.position.x - from.x, entity.thing.position.y - from.y);
+        let along = cx * dx + cy * dy;
+        let side = (cx * dy - cy * dx).abs();
+        if along <= 0.0 || side > entity.radius {
+            continue;
+        }
+        let dist = (along - (entity.radius * entity.radius - side * side).sqrt()).max(0.0);
+        let z_at = z + slope * dist;
+        if dist / range < stop_t && (entity.thing.z..=entity.thing.z + entity.height).contains(&z_at)
+            && best.is_none_or(|(d, _)| dist < d) {
+            best = Some((dist, entity.id));
+        }
+    }
+    match best {
+        Some((_, id)) => TraceHitT { thing: Some(id), wall: None },
+        None => TraceHitT { thing: None, wall: stop_wall },
+    }
+}
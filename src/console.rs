@@ -0,0 +1,511 @@
+use std::fs;
+use sdl2::keyboard::Scancode;
+use crate::{ai, game_state::{self, GameStateT}, items, menu::{self, GameModeT}, player::{self, PlayerT}, renderer::Screen, savegame, text::{self, TextStyleT}, weapons};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const AUTOEXEC_PATH: &str = "autoexec.cfg";
+pub const MAX_OUTPUT_LINES: usize = 256;
+pub const MAX_HISTORY: usize = 64;
+pub const MAX_EXEC_DEPTH: u32 = 8; // Archivos 'exec' anidados (corta los que se llaman entre si)
+pub const HEIGHT_FRAC: f64 = 0.5;  // Fraccion de la pantalla que ocupa abierta
+pub const SLIDE_SPEED: f64 = 4.0;  // Se abre o cierra en 1/4 de segundo
+pub const TEXT_SCALE: i32 = 2;
+pub const BACKGROUND_CLR: u32 = 0x101018;
+pub const BACKGROUND_ALPHA: u8 = 200;
+pub const BORDER_CLR: u32 = 0xA08060;
+pub const TEXT_CLR: u32 = 0xC0C0C0;
+pub const INPUT_CLR: u32 = 0xFFFFFF;
+pub const PROMPT: &str = "] ";
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+pub struct ConsoleT {
+    pub is_open: bool,
+    pub frac: f64,              // 0 = oculta, 1 = abierta del todo (para la animacion)
+    pub input: String,
+    pub output: Vec<String>,
+    pub history: Vec<String>,
+    pub history_pos: Option<usize>, // Entrada del historial que se esta mostrando
+    pub scroll: usize,          // Lineas desplazadas hacia arriba
+    pub exec_depth: u32,        // Archivos 'exec' que se estan ejecutando uno dentro de otro
+}
+    impl ConsoleT {
+        pub fn new() -> Self {
+            ConsoleT {
+                is_open: false,
+                frac: 0.0,
+                input: String::new(),
+                output: Vec::new(),
+                history: Vec::new(),
+                history_pos: None,
+                scroll: 0,
+                exec_depth: 0,
+            }
+        }
+
+        // Agrega un mensaje (tambien sale por la terminal)
+        pub fn print(&mut self, message: &str) {
+            for line in message.lines() {
+                println!("{}", line);
+                self.output.push(line.to_string());
+            }
+            if self.output.len() > MAX_OUTPUT_LINES {
+                let extra = self.output.len() - MAX_OUTPUT_LINES;
+                self.output.drain(0..extra);
+            }
+            self.scroll = 0;
+        }
+    }
+
+#[derive(Clone, Copy)]
+pub enum CvarValueT {
+    Bool(bool),
+    Float(f64),
+}
+
+// Variable de consola: lee y escribe directamente el campo de GameStateT que representa
+pub struct CvarT {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub get: fn(&GameStateT) -> CvarValueT,
+    pub set: fn(&mut GameStateT, CvarValueT),
+}
+
+pub struct CommandT {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub run: fn(&[&str], &mut GameStateT, &mut PlayerT) -> Result<String, String>,
+}
+
+pub const CVARS: [CvarT; 7] = [
+    CvarT {
+        name: "fps_capped", help: "limitar los FPS a fps_max", min: 0.0, max: 1.0,
+        get: |s| CvarValueT::Bool(s.is_fps_capped),
+        set: |s, v| if let CvarValueT::Bool(b) = v { s.is_fps_capped = b },
+    },
+    CvarT {
+        name: "fps_max", help: "FPS objetivo", min: 10.0, max: 1000.0,
+        get: |s| CvarValueT::Float(s.target_fps),
+        set: |s, v| if let CvarValueT::Float(f) = v {
+            s.target_fps = f;
+            s.target_frame_time = 1.0 / f;
+        },
+    },
+    CvarT {
+        name: "mov_speed", help: "velocidad de movimiento (unidades por segundo)", min: 1.0, max: 2000.0,
+        get: |s| CvarValueT::Float(s.speed.mov_speed),
+        set: |s, v| if let CvarValueT::Float(f) = v { s.speed.mov_speed = f },
+    },
+    CvarT {
+        name: "rot_speed", help: "velocidad de giro (radianes por segundo)", min: 0.1, max: 20.0,
+        get: |s| CvarValueT::Float(s.speed.rot_speed),
+        set: |s, v| if let CvarValueT::Float(f) = v { s.speed.rot_speed = f },
+    },
+    CvarT {
+        name: "fov", help: "campo de vision horizontal en grados", min: 30.0, max: 150.0,
+        get: |s| CvarValueT::Float(s.fov.to_degrees()),
+        set: |s, v| if let CvarValueT::Float(f) = v { s.fov = f.to_radians() },
+    },
+    CvarT {
+        name: "item_respawn", help: "segundos hasta que reaparecen los items levantados (0 = nunca)", min: 0.0, max: 600.0,
+        get: |s| CvarValueT::Float(s.item_respawn),
+        set: |s, v| if let CvarValueT::Float(f) = v { s.item_respawn = f },
+    },
+    CvarT {
+        name: "render_scale", help: "fraccion de la resolucion a la que se dibuja", min: 0.25, max: 1.0,
+        get: |s| CvarValueT::Float(s.render_scale),
+        set: |s, v| if let CvarValueT::Float(f) = v { s.render_scale = f },
+    },
+];
+
+pub const COMMANDS: [CommandT; 17] = [
+    CommandT { name: "help", help: "lista los comandos y variables", run: cmd_help },
+    CommandT { name: "map", help: "map <archivo>: carga un mapa", run: cmd_map },
+    CommandT { name: "save", help: "save [ranura]: guarda la partida (1..6 o quick)", run: cmd_save },
+    CommandT { name: "load", help: "load [ranura]: carga una partida guardada", run: cmd_load },
+    CommandT { name: "saves", help: "lista las partidas guardadas", run: cmd_saves },
+    CommandT { name: "noclip", help: "atravesar paredes", run: cmd_noclip },
+    CommandT { name: "god", help: "modo invencible", run: cmd_god },
+    CommandT { name: "fly", help: "volar sin gravedad (sube y baja con las teclas)", run: cmd_fly },
+    CommandT { name: "things", help: "things [sector]: lista los things del sector (por defecto el del jugador)", run: cmd_things },
+    CommandT { name: "kill", help: "mata a todos los enemigos", run: cmd_kill },
+    CommandT { name: "noise", help: "hace ruido donde esta el jugador (prueba emboscadas)", run: cmd_noise },
+    CommandT { name: "give", help: "give <weapons|ammo|keys|health|all|sprite>: da armas, municion, llaves, salud o un item", run: cmd_give },
+    CommandT { name: "screenshot", help: "screenshot [archivo]: guarda una captura BMP", run: cmd_screenshot },
+    CommandT { name: "exec", help: "exec <archivo>: ejecuta un script de comandos", run: cmd_exec },
+    CommandT { name: "cvars", help: "muestra el valor de todas las variables", run: cmd_cvars },
+    CommandT { name: "clear", help: "borra la consola", run: cmd_clear },
+    CommandT { name: "quit", help: "salir del juego", run: cmd_quit },
+];
+
+
+///////////////////////////////// COMANDOS /////////////////////////////////
+fn cmd_help(_args: &[&str], _state: &mut GameStateT, _player: &mut PlayerT) -> Result<String, String> {
+    let mut lines: Vec<String> = COMMANDS.iter().map(|c| format!("{:<12} {}", c.name, c.help)).collect();
+    lines.extend(CVARS.iter().map(|v| format!("{:<12} {}", v.name, v.help)));
+    Ok(lines.join("\n"))
+}
+
+fn cmd_map(args: &[&str], state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let [path] = args else {
+        return Err("uso: map <archivo>".to_string());
+    };
+    game_state::load_level(state, player, path)?;
+    Ok(format!("mapa cargado: {}", path))
+}
+
+fn cmd_save(args: &[&str], state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let slot = match args {
+        [] => savegame::QUICKSAVE_SLOT,
+        [slot] => slot,
+        _ => return Err("uso: save [ranura]".to_string()),
+    };
+    let path = savegame::save_game(state, player, slot)?;
+    Ok(format!("partida guardada: {}", path.display()))
+}
+
+fn cmd_load(args: &[&str], state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let slot = match args {
+        [] => savegame::QUICKSAVE_SLOT,
+        [slot] => slot,
+        _ => return Err("uso: load [ranura]".to_string()),
+    };
+    savegame::load_game(state, player, slot)?;
+    menu::set_mode(state, GameModeT::Playing);
+    Ok(format!("partida cargada: {}", slot))
+}
+
+fn cmd_saves(_args: &[&str], _state: &mut GameStateT, _player: &mut PlayerT) -> Result<String, String> {
+    let slots = savegame::list_slots();
+    if slots.is_empty() {
+        return Ok("no hay partidas guardadas".to_string());
+    }
+    Ok(slots.iter().map(|(slot, description)| format!("{:<6} {}", slot, description)).collect::<Vec<_>>().join("\n"))
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "ON" } else { "OFF" }
+}
+
+fn cmd_noclip(_args: &[&str], _state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    player.is_noclip = !player.is_noclip;
+    Ok(format!("noclip {}", on_off(player.is_noclip)))
+}
+
+fn cmd_god(_args: &[&str], _state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    player.is_god = !player.is_god;
+    Ok(format!("god {}", on_off(player.is_god)))
+}
+
+fn cmd_fly(_args: &[&str], _state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    player.is_flying = !player.is_flying;
+    player.vz = 0.0;
+    Ok(format!("fly {}", on_off(player.is_flying)))
+}
+
+fn cmd_things(args: &[&str], state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let sector = match args {
+        [] => state.sector_at(player.position.x, player.position.y).ok_or("el jugador esta fuera del mapa")?,
+        [sector] => sector.parse::<usize>().ok().filter(|&s| s < state.sectors.len()).ok_or(format!("sector invalido '{}'", sector))?,
+        _ => return Err("uso: things [sector]".to_string()),
+    };
+    let lines: Vec<String> = state.entities.in_sector(sector).map(|e| format!(
+        "#{}.{} {} {:?} ({:.0}, {:.0}) R{} H{}{}", e.id.index, e.id.generation, e.thing.sprite, e.kind,
+        e.thing.position.x, e.thing.position.y, e.radius, e.height, if e.is_solid { " SOLIDO" } else { "" },
+    )).collect();
+    if lines.is_empty() {
+        return Ok(format!("sector {}: sin things", sector));
+    }
+    Ok(lines.join("\n"))
+}
+
+// Los mata con su animacion de muerte (los cadaveres quedan en el mapa)
+fn cmd_kill(_args: &[&str], state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let enemies: Vec<_> = state.entities.iter().filter(|e| ai::is_alive(e)).map(|e| (e.id, e.monster.as_ref().map_or(0, |m| m.health))).collect();
+    for &(id, health) in &enemies {
+        ai::damage_monster(state, player, id, health);
+    }
+    Ok(format!("{} enemigos muertos", enemies.len()))
+}
+
+fn cmd_noise(_args: &[&str], state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let sector = state.sector_at(player.position.x, player.position.y).ok_or("el jugador esta fuera del mapa")?;
+    let (sectors, woken) = ai::noise_alert(state, sector);
+    Ok(format!("el ruido llego a {} sectores y desperto a {} enemigos", sectors, woken))
+}
+
+// Armas y municion (como los trucos de Doom)
+fn cmd_give(args: &[&str], _state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let [item] = args else {
+        return Err("uso: give <weapons|ammo|keys|health|all|sprite>".to_string());
+    };
+    // Un sprite de item (STIM, SHOT, BKEY...) se levanta como si se lo tocara
+    if let Some(info) = items::item_info(&item.to_ascii_uppercase()) {
+        if !items::apply(player, info.effect) {
+            return Err(format!("give: {} no hace falta", info.sprite));
+        }
+        return Ok(format!("recibido: {}", info.message));
+    }
+    let (give_weapons, give_ammo, give_keys, give_health) = match *item {
+        "weapons" => (true, false, false, false),
+        "ammo" => (false, true, false, false),
+        "keys" => (false, false, true, false),
+        "health" => (false, false, false, true),
+        "all" => (true, true, true, true),
+        _ => return Err(format!("give: objeto desconocido '{}' (weapons, ammo, keys, health, all o un sprite de item)", item)),
+    };
+    let arsenal = &mut player.weapons;
+    let mut given = Vec::new();
+    if give_weapons {
+        arsenal.owned = [true; weapons::NUM_WEAPONS];
+        given.extend(weapons::WEAPONS.iter().map(|w| w.name));
+    }
+    if give_ammo {
+        arsenal.ammo = weapons::MAX_AMMO;
+        given.extend(weapons::AMMO_NAMES);
+    }
+    if give_keys {
+        player.keys = [true; player::NUM_KEYS];
+        given.extend(player::KEY_NAMES);
+    }
+    if give_health {
+        player.health = player.health.max(player::MAX_HEALTH);
+        given.push("HEALTH");
+    }
+    Ok(format!("recibido: {}", given.join(", ")))
+}
+
+// Primer nombre libre del estilo screenshot_000.bmp
+fn next_screenshot_path() -> String {
+    (0..1000)
+        .map(|i| format!("screenshot_{:03}.bmp", i))
+        .find(|p| !std::path::Path::new(p).exists())
+        .unwrap_or_else(|| "screenshot.bmp".to_string())
+}
+
+fn cmd_screenshot(args: &[&str], state: &mut GameStateT, _player: &mut PlayerT) -> Result<String, String> {
+    let path = match args {
+        [] => next_screenshot_path(),
+        [path] => path.to_string(),
+        _ => return Err("uso: screenshot [archivo]".to_string()),
+    };
+    // Se cierra la consola para que no salga en la captura
+    state.console.is_open = false;
+    state.console.frac = 0.0;
+    state.screenshot_request = Some(path);
+    Ok(String::new())
+}
+
+fn cmd_exec(args: &[&str], state: &mut GameStateT, player: &mut PlayerT) -> Result<String, String> {
+    let [path] = args else {
+        return Err("uso: exec <archivo>".to_string());
+    };
+    exec_file(state, player, path)?;
+    Ok(String::new())
+}
+
+fn cmd_cvars(_args: &[&str], state: &mut GameStateT, _player: &mut PlayerT) -> Result<String, String> {
+    Ok(CVARS.iter().map(|v| format!("{} = {}", v.name, format_value((v.get)(state)))).collect::<Vec<_>>().join("\n"))
+}
+
+fn cmd_clear(_args: &[&str], state: &mut GameStateT, _player: &mut PlayerT) -> Result<String, String> {
+    state.console.output.clear();
+    Ok(String::new())
+}
+
+fn cmd_quit(_args: &[&str], state: &mut GameStateT, _player: &mut PlayerT) -> Result<String, String> {
+    state.is_running = false;
+    Ok(String::new())
+}
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+fn format_value(value: CvarValueT) -> String {
+    match value {
+        CvarValueT::Bool(b) => (if b { "1" } else { "0" }).to_string(),
+        CvarValueT::Float(f) => format!("{}", (f * 1000.0).round() / 1000.0),
+    }
+}
+
+pub fn find_cvar(name: &str) -> Option<&'static CvarT> {
+    CVARS.iter().find(|v| v.name.eq_ignore_ascii_case(name))
+}
+
+// Asigna una variable desde texto, respetando su tipo y su rango
+pub fn set_cvar(state: &mut GameStateT, name: &str, value: &str) -> Result<String, String> {
+    let cvar = find_cvar(name).ok_or(format!("variable desconocida '{}'", name))?;
+    let parsed = match (cvar.get)(state) {
+        CvarValueT::Bool(_) => match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "on" | "yes" => CvarValueT::Bool(true),
+            "0" | "false" | "off" | "no" => CvarValueT::Bool(false),
+            _ => return Err(format!("{}: valor booleano invalido '{}'", cvar.name, value)),
+        },
+        CvarValueT::Float(_) => {
+            let f: f64 = value.parse().map_err(|_| format!("{}: numero invalido '{}'", cvar.name, value))?;
+            CvarValueT::Float(f.clamp(cvar.min, cvar.max))
+        }
+    };
+    (cvar.set)(state, parsed);
+    Ok(format!("{} = {}", cvar.name, format_value((cvar.get)(state))))
+}
+
+// Ejecuta una linea: un comando, o una variable (sola la muestra, con valor la cambia)
+pub fn execute(state: &mut GameStateT, player: &mut PlayerT, line: &str) {
+    let line = line.split("//").next().unwrap_or("").trim();
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let Some((&name, args)) = tokens.split_first() else { return; };
+
+    let result = if let Some(command) = COMMANDS.iter().find(|c| c.name.eq_ignore_ascii_case(name)) {
+        (command.run)(args, state, player)
+    } else if let Some(cvar) = find_cvar(name) {
+        match args {
+            [] => Ok(format!("{} = {}  ({})", cvar.name, format_value((cvar.get)(state)), cvar.help)),
+            [value] => set_cvar(state, name, value),
+            _ => Err(format!("uso: {} [valor]", cvar.name)),
+        }
+    } else {
+        Err(format!("comando desconocido '{}'", name))
+    };
+
+    match result {
+        Ok(message) if !message.is_empty() => state.console.print(&message),
+        Ok(_) => {}
+        Err(e) => state.console.print(&e),
+    }
+}
+
+// Ejecuta un archivo de comandos, una linea por comando ('//' para comentarios)
+pub fn exec_file(state: &mut GameStateT, player: &mut PlayerT, path: &str) -> Result<(), String> {
+    if state.console.exec_depth >= MAX_EXEC_DEPTH {
+        return Err(format!("exec '{}': demasiados archivos anidados (maximo {})", path, MAX_EXEC_DEPTH));
+    }
+    let source = fs::read_to_string(path).map_err(|e| format!("no se pudo leer '{}': {}", path, e))?;
+    state.console.exec_depth += 1;
+    for line in source.lines() {
+        execute(state, player, line);
+    }
+    state.console.exec_depth -= 1;
+    Ok(())
+}
+
+// Script de arranque opcional
+pub fn run_autoexec(state: &mut GameStateT, player: &mut PlayerT) {
+    if std::path::Path::new(AUTOEXEC_PATH).exists() && let Err(e) = exec_file(state, player, AUTOEXEC_PATH) {
+        eprintln!("{}", e);
+    }
+}
+
+pub fn toggle(console: &mut ConsoleT) {
+    console.is_open = !console.is_open;
+    console.history_pos = None;
+}
+
+// Texto escrito (viene de los eventos TextInput de SDL)
+pub fn type_text(console: &mut ConsoleT, text: &str) {
+    // La tecla de la consola tambien genera texto
+    console.input.extend(text.chars().filter(|c| *c != '`' && *c != '~' && !c.is_control()));
+}
+
+// Completa el nombre de comando o variable con lo que se escribio hasta ahora
+fn complete(console: &mut ConsoleT) {
+    let prefix = console.input.trim_start().to_ascii_lowercase();
+    if prefix.is_empty() || prefix.contains(' ') {
+        return;
+    }
+    let mut matches: Vec<&str> = COMMANDS.iter().map(|c| c.name).chain(CVARS.iter().map(|v| v.name)).filter(|n| n.starts_with(&prefix)).collect();
+    matches.sort();
+    match matches.as_slice() {
+        [] => {}
+        [only] => console.input = format!("{} ", only),
+        [first, rest @ ..] => {
+            // Prefijo comun de todas las opciones
+            let common = rest.iter().fold(first.len(), |len, m| first.bytes().zip(m.bytes()).take(len).take_while(|(a, b)| a == b).count());
+            console.input = first[..common].to_string();
+            console.print(&matches.join("  "));
+        }
+    }
+}
+
+fn history_step(console: &mut ConsoleT, older: bool) {
+    if console.history.is_empty() {
+        return;
+    }
+    let last = console.history.len() - 1;
+    console.history_pos = match (console.history_pos, older) {
+        (None, true) => Some(last),
+        (None, false) => None,
+        (Some(pos), true) => Some(pos.saturating_sub(1)),
+        (Some(pos), false) if pos < last => Some(pos + 1),
+        (Some(_), false) => None,
+    };
+    console.input = console.history_pos.map(|pos| console.history[pos].clone()).unwrap_or_default();
+}
+
+// Teclas especiales con la consola abierta
+pub fn handle_key(scancode: Scancode, state: &mut GameStateT, player: &mut PlayerT) {
+    let console = &mut state.console;
+    match scancode {
+        Scancode::Return | Scancode::KpEnter => {
+            let line = std::mem::take(&mut console.input);
+            console.history_pos = None;
+            console.print(&format!("{}{}", PROMPT, line));
+            if !line.trim().is_empty() {
+                if console.history.last() != Some(&line) {
+                    console.history.push(line.clone());
+                }
+                if console.history.len() > MAX_HISTORY {
+                    console.history.remove(0);
+                }
+                execute(state, player, &line);
+            }
+        }
+        Scancode::Backspace => {
+            console.input.pop();
+        }
+        Scancode::Tab => complete(console),
+        Scancode::Up => history_step(console, true),
+        Scancode::Down => history_step(console, false),
+        Scancode::PageUp => console.scroll = (console.scroll + 4).min(console.output.len()),
+        Scancode::PageDown => console.scroll = console.scroll.saturating_sub(4),
+        Scancode::Escape => toggle(console),
+        _ => {}
+    }
+}
+
+// Animacion de apertura y cierre
+pub fn update(console: &mut ConsoleT, delta_time: f64) {
+    let target = if console.is_open { 1.0 } else { 0.0 };
+    let step = SLIDE_SPEED * delta_time;
+    console.frac = if console.frac < target { (console.frac + step).min(target) } else { (console.frac - step).max(target) };
+}
+
+pub fn draw(screen: &mut Screen, state: &GameStateT) {
+    let console = &state.console;
+    if console.frac <= 0.0 {
+        return;
+    }
+    let font = &state.font;
+    let width = screen.width as i32;
+    let height = (screen.height as f64 * HEIGHT_FRAC * console.frac) as i32;
+    screen.blend_rect(0, 0, width, height, BACKGROUND_CLR, BACKGROUND_ALPHA);
+    screen.line(0, height, width - 1, height, BORDER_CLR);
+
+    // Linea de entrada abajo, con cursor parpadeante
+    let line_h = font.line_height(TEXT_SCALE);
+    let input_y = height - line_h - 2;
+    let cursor = if (state.frame_start / 250).is_multiple_of(2) { "_" } else { "" };
+    let input = format!("{}{}{}", PROMPT, console.input, cursor);
+    text::draw_text(screen, font, 4, input_y, &input, &TextStyleT::new(INPUT_CLR, TEXT_SCALE));
+
+    // Salida de mas nueva a mas vieja hacia arriba
+    let mut y = input_y - line_h;
+    let visible = console.output.len().saturating_sub(console.scroll);
+    for line in console.output[..visible].iter().rev() {
+        if y + line_h < 0 {
+            break;
+        }
+        text::draw_text(screen, font, 4, y, line, &TextStyleT::new(TEXT_CLR, TEXT_SCALE));
+        y -= line_h;
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{ai, entities::ThingKindT, game_state::GameStateT, intermission, player::PlayerT, renderer::{RenderInfoT, Screen}, text::{self, AlignT, FontT, TextStyleT}};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const TEXT_CLR: u32 = 0x00FF00;
+pub const TEXT_SCALE: i32 = 2;
+pub const SMOOTHING: f64 = 0.1; // Peso del ultimo frame en los promedios
+
+// Colores de las ventanas de recorte segun la profundidad del portal
+const CLIP_COLORS: [u32; 6] = [0xFF0000, 0xFF8000, 0xFFFF00, 0x00FF00, 0x00FFFF, 0xFF00FF];
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+pub struct DebugT {
+    pub show_clip: bool, // Ver las ventanas de recorte de los portales
+    pub fps: f64,
+    pub frame_ms: f64,   // Trabajo del frame sin contar la espera
+    pub sim_ms: f64,
+    pub render_ms: f64,
+    pub tics: u32,       // Tics simulados en el ultimo frame
+    pub sectors_visited: usize,
+    pub walls_visited: usize,
+    pub walls_drawn: usize,
+}
+    impl DebugT {
+        pub fn new() -> Self {
+            DebugT {
+                show_clip: false,
+                fps: 0.0,
+                frame_ms: 0.0,
+                sim_ms: 0.0,
+                render_ms: 0.0,
+                tics: 0,
+                sectors_visited: 0,
+                walls_visited: 0,
+                walls_drawn: 0,
+            }
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+fn smooth(avg: &mut f64, value: f64) {
+    *avg = if *avg == 0.0 { value } else { *avg + (value - *avg) * SMOOTHING };
+}
+
+// Guarda los tiempos del frame (en segundos) para mostrarlos promediados
+pub fn record_frame(debug: &mut DebugT, delta_time: f64, frame: f64, sim: f64, render: f64, tics: u32) {
+    if delta_time > 0.0 {
+        smooth(&mut debug.fps, 1.0 / delta_time);
+    }
+    smooth(&mut debug.frame_ms, frame * 1000.0);
+    smooth(&mut debug.sim_ms, sim * 1000.0);
+    smooth(&mut debug.render_ms, render * 1000.0);
+    debug.tics = tics;
+}
+
+pub fn record_render(debug: &mut DebugT, info: &RenderInfoT) {
+    debug.sectors_visited = info.sectors_visited;
+    debug.walls_visited = info.walls_visited;
+    debug.walls_drawn = info.drawn_walls.len();
+}
+
+// Marca en cada columna los bordes de cada ventana por la que se vio a traves de un portal
+pub fn draw_clip_windows(screen: &mut Screen, info: &RenderInfoT) {
+    for (x, entries) in info.clip_log.iter().enumerate() {
+        for (level, entry) in entries.iter().enumerate() {
+            if entry.ytop > entry.ybot {
+                continue;
+            }
+            let color = CLIP_COLORS[level % CLIP_COLORS.len()];
+            screen.put_pixel(x as i32, entry.ytop, color);
+            screen.put_pixel(x as i32, entry.ybot, color);
+        }
+    }
+}
+
+// Una linea del overlay, con sombra para que se lea sobre cualquier fondo
+fn draw_line(screen: &mut Screen, font: &FontT, row: i32, line: &str) {
+    let y = 4 + row * font.line_height(TEXT_SCALE);
+    text::draw_shadowed(screen, font, 4, y, line, &TextStyleT::new(TEXT_CLR, TEXT_SCALE));
+}
+
+pub fn draw(screen: &mut Screen, game_state: &GameStateT, player: &PlayerT) {
+    let debug = &game_state.debug;
+    let sector = match game_state.sector_at(player.position.x, player.position.y) {
+        Some(index) => index.to_string(),
+        None => "-".to_string(),
+    };
+    let font = &game_state.font;
+    let fps = format!("{:.0} FPS", debug.fps);
+    let style = TextStyleT::new(TEXT_CLR, TEXT_SCALE * 2).aligned(AlignT::Right);
+    text::draw_text(screen, font, screen.width as i32 - 4, 4, &fps, &style);
+
+    let lines = [
+        format!("TIC {}", game_state.tic),
+        format!("FRAME {:.2}MS  SIM {:.2}MS ({})  RENDER {:.2}MS", debug.frame_ms, debug.sim_ms, debug.tics, debug.render_ms),
+        format!("X {:.1}  Y {:.1}  Z {:.1}  ANG {:.1}", player.position.x, player.position.y, player.z, player.dir_angle.to_degrees().rem_euclid(360.0)),
+        format!("SECTOR {}", sector),
+        format!("SECTORS {}  WALLS {} ({} DRAWN)", debug.sectors_visited, debug.walls_visited, debug.walls_drawn),
+        format!("ENEMIES {}/{}  ITEMS {}  DECORATIONS {}", game_state.entities.iter().filter(|e| ai::is_alive(e)).count(), game_state.entities.count(ThingKindT::Enemy),
+            game_state.entities.count(ThingKindT::Item), game_state.entities.count(ThingKindT::Decoration)),
+        intermission::summary(game_state),
+    ];
+    for (row, line) in lines.iter().enumerate() {
+        draw_line(screen, font, row as i32, line);
+    }
+}
@@ -0,0 +1,169 @@
+use std::fs;
+use crate::{keyboard::KeystatesT, player::PlayerT, typedefs::Vec2T};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const DEMO_HEADER: &str = "DEMO 1";
+
+// Bit de cada tecla de movimiento en los frames de la demo
+const KEY_FORWARD: u32 = 1 << 0;
+const KEY_BACKWARD: u32 = 1 << 1;
+const KEY_LEFT: u32 = 1 << 2;
+const KEY_RIGHT: u32 = 1 << 3;
+const KEY_STRAFE_LEFT: u32 = 1 << 4;
+const KEY_STRAFE_RIGHT: u32 = 1 << 5;
+const KEY_UP: u32 = 1 << 6;
+const KEY_DOWN: u32 = 1 << 7;
+const KEY_FIRE: u32 = 1 << 8;
+const WEAPON_SHIFT: u32 = 9; // Arma elegida en el frame + 1 (0 = ninguna) en los bits 9..12
+const WEAPON_MASK: u32 = 0xF;
+const KEY_USE: u32 = 1 << 13;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy, PartialEq)]
+pub enum DemoModeT {
+    Recording,
+    Playing,
+}
+
+// Entrada de un frame jugado: con el mismo delta_time la simulacion se repite igual
+pub struct DemoFrameT {
+    pub delta_time: f64,
+    pub keys: u32,
+    pub turn: f64, // Giro del mouse en radianes
+}
+
+pub struct DemoT {
+    pub path: String,
+    pub mode: DemoModeT,
+    pub map_path: String,
+    pub start: Vec2T,
+    pub start_angle: f64,
+    pub frames: Vec<DemoFrameT>,
+    pub position: usize, // Proximo frame a reproducir
+}
+    impl DemoT {
+        // Demo vacia que empieza donde esta el jugador
+        pub fn record(path: &str, map_path: &str, player: &PlayerT) -> Self {
+            DemoT {
+                path: path.to_string(),
+                mode: DemoModeT::Recording,
+                map_path: map_path.to_string(),
+                start: player.position.clone(),
+                start_angle: player.dir_angle,
+                frames: Vec::new(),
+                position: 0,
+            }
+        }
+
+        // Formato: cabecera, 'map <ruta>', 'start <x> <y> <angulo>' y una linea '<dt> <teclas> <giro>' por frame
+        pub fn parse(path: &str, source: &str) -> Result<Self, String> {
+            let mut lines = source.lines().map(str::trim).filter(|line| !line.is_empty());
+            if lines.next() != Some(DEMO_HEADER) {
+                return Err(format!("{}: no es una demo ({} esperado)", path, DEMO_HEADER));
+            }
+            let map_path = lines.next().and_then(|line| line.strip_prefix("map "))
+                .ok_or(format!("{}: falta la linea 'map'", path))?;
+            let start: Vec<f64> = lines.next().and_then(|line| line.strip_prefix("start "))
+                .ok_or(format!("{}: falta la linea 'start'", path))?
+                .split_whitespace().map(|v| v.parse::<f64>()).collect::<Result<_, _>>()
+                .map_err(|_| format!("{}: linea 'start' invalida", path))?;
+            if start.len() != 3 {
+                return Err(format!("{}: 'start' necesita x, y y angulo", path));
+            }
+
+            let mut frames = Vec::new();
+            for (index, line) in lines.enumerate() {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let frame = match fields[..] {
+                    [dt, keys, turn] => match (dt.parse(), keys.parse(), turn.parse()) {
+                        (Ok(delta_time), Ok(keys), Ok(turn)) => Some(DemoFrameT { delta_time, keys, turn }),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                frames.push(frame.ok_or(format!("{}: frame {} invalido", path, index + 1))?);
+            }
+            Ok(DemoT {
+                path: path.to_string(),
+                mode: DemoModeT::Playing,
+                map_path: map_path.trim().to_string(),
+                start: Vec2T::new(start[0], start[1]),
+                start_angle: start[2],
+                frames,
+                position: 0,
+            })
+        }
+
+        pub fn load(path: &str) -> Result<Self, String> {
+            let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            DemoT::parse(path, &source)
+        }
+
+        pub fn serialize(&self) -> String {
+            let mut out = format!("{}\nmap {}\n", DEMO_HEADER, self.map_path);
+            out += &format!("start {} {} {}\n", self.start.x, self.start.y, self.start_angle);
+            for frame in &self.frames {
+                out += &format!("{} {} {}\n", frame.delta_time, frame.keys, frame.turn);
+            }
+            out
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.mode == DemoModeT::Playing && self.position >= self.frames.len()
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+fn pack_keys(keystates: &KeystatesT) -> u32 {
+    let keys = [
+        (keystates.forward, KEY_FORWARD), (keystates.backward, KEY_BACKWARD),
+        (keystates.left, KEY_LEFT), (keystates.right, KEY_RIGHT),
+        (keystates.strafe_left, KEY_STRAFE_LEFT), (keystates.strafe_right, KEY_STRAFE_RIGHT),
+        (keystates.up, KEY_UP), (keystates.down, KEY_DOWN), (keystates.fire, KEY_FIRE),
+        (keystates.activate, KEY_USE),
+    ];
+    let weapon = keystates.weapon_select.map_or(0, |w| w as u32 + 1) << WEAPON_SHIFT;
+    keys.iter().filter(|(down, _)| *down).fold(weapon, |bits, (_, bit)| bits | bit)
+}
+
+fn unpack_keys(keystates: &mut KeystatesT, keys: u32) {
+    keystates.forward = keys & KEY_FORWARD != 0;
+    keystates.backward = keys & KEY_BACKWARD != 0;
+    keystates.left = keys & KEY_LEFT != 0;
+    keystates.right = keys & KEY_RIGHT != 0;
+    keystates.strafe_left = keys & KEY_STRAFE_LEFT != 0;
+    keystates.strafe_right = keys & KEY_STRAFE_RIGHT != 0;
+    keystates.up = keys & KEY_UP != 0;
+    keystates.down = keys & KEY_DOWN != 0;
+    keystates.fire = keys & KEY_FIRE != 0;
+    keystates.activate = keys & KEY_USE != 0;
+    keystates.weapon_select = match (keys >> WEAPON_SHIFT) & WEAPON_MASK {
+        0 => None,
+        weapon => Some(weapon as usize - 1),
+    };
+}
+
+// Graba la entrada del frame o la reemplaza por la grabada (incluido su delta_time)
+pub fn update(demo: &mut DemoT, keystates: &mut KeystatesT, delta_time: &mut f64) {
+    match demo.mode {
+        DemoModeT::Recording => {
+            demo.frames.push(DemoFrameT { delta_time: *delta_time, keys: pack_keys(keystates), turn: keystates.mouse_turn });
+        }
+        DemoModeT::Playing => {
+            let Some(frame) = demo.frames.get(demo.position) else {
+                unpack_keys(keystates, 0);
+                keystates.mouse_turn = 0.0;
+                return;
+            };
+            *delta_time = frame.delta_time;
+            unpack_keys(keystates, frame.keys);
+            keystates.mouse_turn = frame.turn;
+            demo.position += 1;
+        }
+    }
+}
+
+pub fn save(demo: &DemoT) -> Result<(), String> {
+    fs::write(&demo.path, demo.serialize()).map_err(|e| format!("{}: {}", demo.path, e))
+}
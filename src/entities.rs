@@ -0,0 +1,287 @@
+use crate::{ai::{self, MonsterT}, renderer::SectorT, sprites::SpriteThingT, weapons::ProjectileT};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+// Tamaño de los things que no estan en la tabla
+pub const DEFAULT_RADIUS: f64 = 16.0;
+pub const DEFAULT_HEIGHT: f64 = 16.0;
+
+// Datos de cada tipo de thing segun su sprite (valores de Doom)
+const THING_TYPES: [ThingTypeT; 32] = [
+    ThingTypeT { sprite: "POSS", kind: ThingKindT::Enemy, radius: 20.0, height: 56.0, is_solid: true, is_counted: true, anim: None },
+    ThingTypeT { sprite: "SPOS", kind: ThingKindT::Enemy, radius: 20.0, height: 56.0, is_solid: true, is_counted: true, anim: None },
+    ThingTypeT { sprite: "TROO", kind: ThingKindT::Enemy, radius: 20.0, height: 56.0, is_solid: true, is_counted: true, anim: None },
+    ThingTypeT { sprite: "SARG", kind: ThingKindT::Enemy, radius: 30.0, height: 56.0, is_solid: true, is_counted: true, anim: None },
+    ThingTypeT { sprite: "BAR1", kind: ThingKindT::Decoration, radius: 10.0, height: 42.0, is_solid: true, is_counted: false, anim: Some(("AB", 6)) },
+    ThingTypeT { sprite: "COLU", kind: ThingKindT::Decoration, radius: 16.0, height: 48.0, is_solid: true, is_counted: false, anim: None },
+    ThingTypeT { sprite: "ELEC", kind: ThingKindT::Decoration, radius: 16.0, height: 128.0, is_solid: true, is_counted: false, anim: None },
+    ThingTypeT { sprite: "STIM", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "MEDI", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "ARM1", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: Some(("AB", 6)) },
+    ThingTypeT { sprite: "ARM2", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: Some(("AB", 6)) },
+    ThingTypeT { sprite: "BON1", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: true, anim: Some(("ABCDCB", 6)) },
+    ThingTypeT { sprite: "CLIP", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "SHEL", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "BON2", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: true, anim: Some(("ABCDCB", 6)) },
+    ThingTypeT { sprite: "SOUL", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: true, anim: Some(("ABCDCB", 6)) },
+    ThingTypeT { sprite: "AMMO", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "SBOX", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "ROCK", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "BROK", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "CELL", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "CELP", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "SHOT", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "MGUN", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "LAUN", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "PLAS", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "BKEY", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: Some(("AB", 10)) },
+    ThingTypeT { sprite: "YKEY", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: Some(("AB", 10)) },
+    ThingTypeT { sprite: "RKEY", kind: ThingKindT::Item, radius: 20.0, height: 16.0, is_solid: false, is_counted: false, anim: Some(("AB", 10)) },
+    ThingTypeT { sprite: "MISL", kind: ThingKindT::Projectile, radius: 11.0, height: 8.0, is_solid: false, is_counted: false, anim: None },
+    ThingTypeT { sprite: "PLSS", kind: ThingKindT::Projectile, radius: 13.0, height: 8.0, is_solid: false, is_counted: false, anim: Some(("AB", 6)) },
+    ThingTypeT { sprite: "BAL1", kind: ThingKindT::Projectile, radius: 6.0, height: 8.0, is_solid: false, is_counted: false, anim: Some(("AB", 4)) },
+];
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ThingKindT {
+    Enemy,
+    Item,
+    Decoration,
+    Projectile,
+}
+
+struct ThingTypeT {
+    sprite: &'static str,
+    kind: ThingKindT,
+    radius: f64,
+    height: f64,
+    is_solid: bool,                     // Bloquea el paso
+    is_counted: bool,                   // Entra en los porcentajes del nivel (MF_COUNTKILL y MF_COUNTITEM)
+    anim: Option<(&'static str, i32)>,  // Cuadros en orden y tics por cuadro (los enemigos los maneja la IA)
+}
+
+// Referencia a una entidad: deja de valer cuando se borra aunque su lugar se reuse
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct EntityIdT {
+    pub index: u32,
+    pub generation: u32,
+}
+
+// Componente de animacion en bucle
+#[derive(Clone)]
+pub struct AnimT {
+    pub frames: &'static str,
+    pub tics: i32,  // Duracion de cada cuadro
+    pub step: usize,
+    pub count: i32, // Tics que le quedan al cuadro actual
+}
+
+pub struct EntityT {
+    pub id: EntityIdT,
+    pub kind: ThingKindT,
+    pub thing: SpriteThingT, // Posicion, altura, angulo y sprite a dibujar
+    pub sector: usize,
+    pub radius: f64,
+    pub height: f64,
+    pub is_solid: bool,
+    pub is_counted: bool,
+    pub anim: Option<AnimT>,
+    pub monster: Option<MonsterT>, // IA de los enemigos
+    pub projectile: Option<ProjectileT>,
+}
+
+// Muertes e items del nivel: los totales salen de los things del mapa que cuentan
+#[derive(Clone, Copy)]
+pub struct TallyT {
+    pub kills: i32,
+    pub total_kills: i32,
+    pub items: i32,
+    pub total_items: i32,
+}
+    impl TallyT {
+        pub fn new() -> Self {
+            TallyT { kills: 0, total_kills: 0, items: 0, total_items: 0 }
+        }
+    }
+
+struct SlotT {
+    generation: u32,
+    entity: Option<EntityT>,
+}
+
+// Arena de entidades con ids generacionales y una lista por sector
+pub struct EntitiesT {
+    slots: Vec<SlotT>,
+    free: Vec<u32>,
+    by_sector: Vec<Vec<EntityIdT>>,
+    pub tally: TallyT,
+}
+    impl EntitiesT {
+        pub fn new() -> Self {
+            EntitiesT { slots: Vec::new(), free: Vec::new(), by_sector: Vec::new(), tally: TallyT::new() }
+        }
+
+        // Borra todo y prepara las listas para un mapa de num_sectors sectores
+        pub fn reset(&mut self, num_sectors: usize) {
+            self.slots.clear();
+            self.free.clear();
+            self.by_sector = vec![Vec::new(); num_sectors];
+            self.tally = TallyT::new();
+        }
+
+        pub fn spawn(&mut self, thing: SpriteThingT, sector: usize) -> EntityIdT {
+            let info = THING_TYPES.iter().find(|t| t.sprite == thing.sprite);
+            let anim = info.and_then(|t| t.anim).and_then(|(frames, tics)| {
+                // Un cuadro fijo pedido por el mapa que no es de la animacion la desactiva
+                let step = frames.find(thing.frame)?;
+                Some(AnimT { frames, tics, step, count: tics })
+            });
+            let monster = ai::spawn(&thing.sprite);
+            let index = match self.free.pop() {
+                Some(index) => index,
+                None => {
+                    self.slots.push(SlotT { generation: 0, entity: None });
+                    self.slots.len() as u32 - 1
+                }
+            };
+            let slot = &mut self.slots[index as usize];
+            let id = EntityIdT { index, generation: slot.generation };
+            slot.entity = Some(EntityT {
+                id,
+                kind: info.map_or(ThingKindT::Decoration, |t| t.kind),
+                thing,
+                sector,
+                radius: info.map_or(DEFAULT_RADIUS, |t| t.radius),
+                height: info.map_or(DEFAULT_HEIGHT, |t| t.height),
+                is_solid: info.is_some_and(|t| t.is_solid),
+                is_counted: info.is_some_and(|t| t.is_counted),
+                anim,
+                monster,
+                projectile: None,
+            });
+            if let Some(list) = self.by_sector.get_mut(sector) {
+                list.push(id);
+            }
+            id
+        }
+
+        pub fn remove(&mut self, id: EntityIdT) -> Option<EntityT> {
+            let slot = self.slots.get_mut(id.index as usize).filter(|s| s.generation == id.generation)?;
+            let entity = slot.entity.take()?;
+            slot.generation += 1;
+            self.free.push(id.index);
+            if let Some(list) = self.by_sector.get_mut(entity.sector) {
+                list.retain(|&other| other != id);
+            }
+            Some(entity)
+        }
+
+        // Un monstruo que acaba de morir (lo llama la IA una sola vez por muerte)
+        pub fn count_kill(&mut self, id: EntityIdT) {
+            if self.get(id).is_some_and(|e| e.is_counted) {
+                self.tally.kills += 1;
+            }
+        }
+
+        // Saca un item levantado y lo suma a los del nivel si cuenta
+        pub fn pick_up(&mut self, id: EntityIdT) -> Option<EntityT> {
+            let entity = self.remove(id)?;
+            if entity.is_counted {
+                self.tally.items += 1;
+            }
+            Some(entity)
+        }
+
+        pub fn get(&self, id: EntityIdT) -> Option<&EntityT> {
+            self.slots.get(id.index as usize).filter(|s| s.generation == id.generation)?.entity.as_ref()
+        }
+
+        pub fn get_mut(&mut self, id: EntityIdT) -> Option<&mut EntityT> {
+            self.slots.get_mut(id.index as usize).filter(|s| s.generation == id.generation)?.entity.as_mut()
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = &EntityT> {
+            self.slots.iter().filter_map(|s| s.entity.as_ref())
+        }
+
+        pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut EntityT> {
+            self.slots.iter_mut().filter_map(|s| s.entity.as_mut())
+        }
+
+        pub fn count(&self, kind: ThingKindT) -> usize {
+            self.iter().filter(|e| e.kind == kind).count()
+        }
+
+        // Entidades que estan dentro de un sector
+        pub fn in_sector(&self, sector: usize) -> impl Iterator<Item = &EntityT> {
+            self.by_sector.get(sector).into_iter().flatten().filter_map(|&id| self.get(id))
+        }
+
+        // Actualiza la lista por sector despues de mover una entidad
+        pub fn relink(&mut self, id: EntityIdT, sector: usize) {
+            let Some(entity) = self.get_mut(id) else { return; };
+            let old = entity.sector;
+            if old == sector {
+                return;
+            }
+            entity.sector = sector;
+            if let Some(list) = self.by_sector.get_mut(old) {
+                list.retain(|&other| other != id);
+            }
+            if let Some(list) = self.by_sector.get_mut(sector) {
+                list.push(id);
+            }
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+// Sector que contiene un punto
+pub fn find_sector(sectors: &[SectorT], x: f64, y: f64) -> Option<usize> {
+    sectors.iter().position(|s| s.contains(x, y))
+}
+
+// Crea las entidades de los things del mapa (los que quedan fuera de todo sector se descartan)
+pub fn spawn_map_things(entities: &mut EntitiesT, sectors: &[SectorT], things: Vec<SpriteThingT>) {
+    entities.reset(sectors.len());
+    for thing in things {
+        let Some(sector) = find_sector(sectors, thing.position.x, thing.position.y) else { continue; };
+        let id = entities.spawn(thing, sector);
+        // Solo los del mapa suman a los totales (no los items que reaparecen)
+        if let Some(entity) = entities.get(id).filter(|e| e.is_counted) {
+            match entity.kind {
+                ThingKindT::Enemy => entities.tally.total_kills += 1,
+                ThingKindT::Item => entities.tally.total_items += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+fn animate(anim: &mut AnimT, thing: &mut SpriteThingT) {
+    anim.count -= 1;
+    if anim.count > 0 {
+        return;
+    }
+    let frames = anim.frames.as_bytes();
+    anim.step = (anim.step + 1) % frames.len();
+    anim.count = anim.tics;
+    thing.frame = frames[anim.step] as char;
+}
+
+// Avanza un tic todas las entidades y mantiene al dia en que sector esta cada una
+pub fn tick(entities: &mut EntitiesT, sectors: &[SectorT]) {
+    let mut moved = Vec::new();
+    for entity in entities.iter_mut() {
+        if let Some(anim) = &mut entity.anim {
+            animate(anim, &mut entity.thing);
+        }
+        let (x, y) = (entity.thing.position.x, entity.thing.position.y);
+        if !sectors[entity.sector].contains(x, y) && let Some(sector) = find_sector(sectors, x, y) {
+            moved.push((entity.id, sector));
+        }
+    }
+    for (id, sector) in moved {
+        entities.relink(id, sector);
+    }
+}
@@ -1,5 +1,11 @@
 use sdl2::TimerSubsystem;
-use crate::renderer::SectorT;
+use crate::{ai, automap::AutomapT, console::ConsoleT, demo::DemoT, menu::{GameModeT, MenuT}, debug::DebugT, entities::{self, EntitiesT}, hud::HudT, intermission::{self, IntermissionT, LevelStatsT}, items::{self, RespawnT}, keyboard::SpeedT, lighting, map, movers, player::{self, PlayerT}, renderer::SectorT, settings::SettingsT, sprites::SpritesT, text::FontT, textures::{self, TexturesT}, typedefs::Vec2T, utils::GameRngT, wad::WadT, weapons};
+
+// Frecuencia fija de la simulacion (tics por segundo, igual que Doom)
+pub const TICRATE: f64 = 35.0;
+pub const MAX_TICS_PER_FRAME: u32 = 10; // Evita la espiral de la muerte si un frame tarda mucho
+pub const DEFAULT_MAP: &str = "maps/demo.map";
+pub const RNG_SEED: u32 = 0x1D00; // Cada nivel empieza con la misma semilla (las demos se repiten igual)
 
 ///////////////////////////////// STRUCTS /////////////////////////////////
 pub struct GameStateT {
@@ -10,29 +16,95 @@ pub struct GameStateT {
     pub target_frame_time: f64,
     pub delta_time: f64,
     pub is_running: bool,
+    pub mode: GameModeT,
+    pub menu: MenuT,
     pub is_paused: bool,
     pub is_fps_capped: bool,
+    pub is_fullscreen: bool,
+    pub mouse_sensitivity: f64,
+    pub volume: u32, // 0..100
+    pub speed: SpeedT,
+    pub fov: f64,          // Campo de vision horizontal en radianes
+    pub render_scale: f64, // Fraccion de la resolucion de la ventana a la que se dibuja
     pub state_show_map: bool,
+    pub automap: AutomapT,
     pub is_debug_mode: bool,
+    pub debug: DebugT,
+    pub is_palette_mode: bool, // Render en 8 bits con paleta
     pub sectors: Vec<SectorT>,
+    pub wad: Option<WadT>,
+    pub textures: TexturesT,
+    pub sprites: SpritesT,
+    pub entities: EntitiesT, // Enemigos, items y decoraciones
+    pub font: FontT,
+    pub console: ConsoleT,
+    pub map_path: String,
+    pub screenshot_request: Option<String>, // Se guarda al terminar el proximo frame
+    pub demo: Option<DemoT>, // Demo que se esta grabando o reproduciendo
+    pub tic: u64, // Tics de simulacion transcurridos
+    pub tic_accumulator: f64,
+    pub rng: GameRngT, // Azar de la simulacion
+    pub hud: HudT,
+    pub item_respawn: f64,       // Segundos hasta que reaparece un item levantado (0 = nunca, como en un solo jugador)
+    pub respawns: Vec<RespawnT>, // Items levantados esperando para reaparecer
+    pub stats: LevelStatsT,      // Muertes, items y secretos del nivel en curso
+    pub episode: Vec<String>,    // Mapas en orden (vacio = un solo mapa suelto)
+    pub intermission: Option<IntermissionT>, // Resumen del nivel que se acaba de terminar
+    pub settings: SettingsT, // Lo que hay en settings.cfg (sin lo que pisa la linea de comandos)
 }
     impl GameStateT {
-        pub fn new(scrnw: u32, scrnh: u32, target_fps: f64) -> Self {
+        // Estado inicial con la configuracion guardada del usuario
+        pub fn new(settings: &SettingsT) -> Self {
             GameStateT {
                 frame_start: 0,
-                scrn_w: scrnw,
-                scrn_h: scrnh,
-                target_fps: target_fps as f64,
-                target_frame_time: 1.0 / target_fps as f64,
-                delta_time: 1.0 / target_fps as f64,
+                scrn_w: settings.width,
+                scrn_h: settings.height,
+                target_fps: settings.target_fps,
+                target_frame_time: 1.0 / settings.target_fps,
+                delta_time: 1.0 / settings.target_fps,
                 is_running: true,
-                is_paused: false,
-                is_fps_capped: false,
+                mode: GameModeT::Title,
+                menu: MenuT::new(),
+                is_paused: true,
+                is_fps_capped: settings.fps_capped,
+                is_fullscreen: settings.fullscreen,
+                mouse_sensitivity: settings.mouse_sensitivity,
+                volume: settings.volume,
+                speed: SpeedT::new(),
+                fov: settings.fov.to_radians(),
+                render_scale: settings.render_scale,
                 state_show_map: false,
+                automap: AutomapT::new(),
                 is_debug_mode: false,
+                debug: DebugT::new(),
+                is_palette_mode: false,
                 sectors: Vec::new(),
+                wad: None,
+                textures: TexturesT::new(),
+                sprites: SpritesT::new(),
+                entities: EntitiesT::new(),
+                font: FontT::builtin(),
+                console: ConsoleT::new(),
+                map_path: String::new(),
+                screenshot_request: None,
+                demo: None,
+                tic: 0,
+                tic_accumulator: 0.0,
+                rng: GameRngT::new(RNG_SEED),
+                hud: HudT::new(),
+                item_respawn: 0.0,
+                respawns: Vec::new(),
+                stats: LevelStatsT::new(),
+                episode: Vec::new(),
+                intermission: None,
+                settings: settings.clone(),
             }
         }
+
+        // Indice del sector que contiene el punto (x, y)
+        pub fn sector_at(&self, x: f64, y: f64) -> Option<usize> {
+            entities::find_sector(&self.sectors, x, y)
+        }
     }
 
 
@@ -45,8 +117,70 @@ pub fn frame_end(timer_subsystem: &TimerSubsystem, state: &mut GameStateT) {
     let delta_time: f64 = (timer_subsystem.ticks() - state.frame_start) as f64 / 1000.0;
     state.delta_time = delta_time;
 
-    if state.delta_time < state.target_frame_time {
+    if state.is_fps_capped && state.delta_time < state.target_frame_time {
         std::thread::sleep(std::time::Duration::from_secs_f64(state.target_frame_time - state.delta_time));
         state.delta_time = state.target_frame_time;
     }
-}
\ No newline at end of file
+}
+
+// Cantidad de tics de simulacion que corresponden al tiempo del ultimo frame
+pub fn pending_tics(state: &mut GameStateT) -> u32 {
+    state.tic_accumulator += state.delta_time * TICRATE;
+    let tics = (state.tic_accumulator as u32).min(MAX_TICS_PER_FRAME);
+    state.tic_accumulator -= tics as f64;
+    state.tic_accumulator = state.tic_accumulator.min(1.0);
+    tics
+}
+
+// Tamaño del framebuffer segun la escala de render
+pub fn render_size(state: &GameStateT) -> (u32, u32) {
+    let w = ((state.scrn_w as f64 * state.render_scale) as u32).max(1);
+    let h = ((state.scrn_h as f64 * state.render_scale) as u32).max(1);
+    (w, h)
+}
+
+// Carga un mapa, sus texturas, y pone al jugador en el inicio
+pub fn load_level(state: &mut GameStateT, player: &mut PlayerT, path: &str) -> Result<(), String> {
+    let map = map::load_map(path)?;
+    textures::load_map_textures(&mut state.textures, state.wad.as_ref(), &map.sectors);
+    state.sectors = map.sectors;
+    entities::spawn_map_things(&mut state.entities, &state.sectors, map.things);
+    state.map_path = path.to_string();
+    state.automap.seen.clear();
+    state.rng = GameRngT::new(RNG_SEED);
+    state.respawns.clear();
+    state.hud = HudT::new();
+    state.stats = intermission::count_level(&state.sectors, state.tic);
+    state.intermission = None;
+    place_player(state, player, map.start, map.start_angle);
+    Ok(())
+}
+
+// Vuelve a empezar el nivel actual con un jugador nuevo (al morir)
+pub fn restart_level(state: &mut GameStateT, player: &mut PlayerT) -> Result<(), String> {
+    let path = state.map_path.clone();
+    *player = PlayerT::new(0.0, 0.0, 0.0, 0.0);
+    load_level(state, player, &path)
+}
+
+// Pone al jugador en un punto del mapa, a la altura de los ojos sobre el piso
+pub fn place_player(state: &GameStateT, player: &mut PlayerT, position: Vec2T, angle: f64) {
+    player.position = position;
+    player.dir_angle = angle;
+    if let Some(sector) = state.sector_at(player.position.x, player.position.y) {
+        player.z = state.sectors[sector].floor_z() + player::EYE_HEIGHT;
+    }
+    player.vz = 0.0;
+}
+
+// Avanza un tic el mundo
+pub fn tick(state: &mut GameStateT, player: &mut PlayerT) {
+    lighting::update(&mut state.sectors, &mut state.rng);
+    movers::tick(state, player);
+    weapons::tick(state, player);
+    ai::tick(state, player);
+    entities::tick(&mut state.entities, &state.sectors);
+    items::tick(state);
+    player::tick(state, player);
+    state.tic += 1;
+}
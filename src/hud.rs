@@ -0,0 +1,148 @@
+use std::f64::consts::{PI, TAU};
+use crate::{game_state::{GameStateT, TICRATE}, player::{self, PlayerT, NUM_KEYS}, renderer::Screen, text::{self, AlignT, FontT, TextStyleT}, textures, weapons::{self, WEAPONS}};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+// Las armas se dibujan en una pantalla virtual de 320x200 escalada a la altura real
+pub const BASE_W: f64 = 320.0;
+pub const BASE_H: f64 = 200.0;
+pub const WEAPON_TOP: f64 = 32.0;     // Altura del arma en reposo (como WEAPONTOP)
+pub const WEAPON_BOTTOM: f64 = 128.0; // Arma guardada, fuera de la vista
+pub const BOB_PERIOD: f64 = 2.0 * TICRATE; // Tics de un vaiven completo de lado a lado
+
+pub const NUMBER_CLR: u32 = 0xE00000;
+pub const LABEL_CLR: u32 = 0xC0C0C0;
+pub const LOW_AMMO_CLR: u32 = 0xFCFC00;
+pub const LABEL_SCALE: i32 = 1;
+pub const NUMBER_SCALE: i32 = 3;
+pub const MARGIN: i32 = 6; // En pixeles de la pantalla virtual
+pub const MESSAGE_TICS: u64 = 4 * TICRATE as u64; // Lo que dura un mensaje arriba a la izquierda
+pub const MESSAGE_CLR: u32 = 0xE00000;
+pub const MESSAGE_SCALE: i32 = 2;
+
+pub const KEY_COLORS: [u32; NUM_KEYS] = [0x2040FC, 0xFCE000, 0xE00000];
+const KEY_SIZE: i32 = 6;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+pub struct HudT {
+    pub message: String, // Ultimo aviso (items levantados)
+    pub message_tic: u64, // Tic en que aparecio
+}
+    impl HudT {
+        pub fn new() -> Self {
+            HudT { message: String::new(), message_tic: 0 }
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+pub fn show_message(hud: &mut HudT, tic: u64, message: &str) {
+    hud.message = message.to_string();
+    hud.message_tic = tic;
+}
+
+// Pixeles reales por pixel de la pantalla virtual (al menos 1)
+fn unit(screen: &Screen) -> i32 {
+    ((screen.height as f64 / BASE_H) as i32).max(1)
+}
+
+// Dibuja un parche de sprite con sus offsets de Doom en la posicion (sx, sy) de la pantalla virtual
+fn draw_psprite(screen: &mut Screen, game_state: &GameStateT, sprite: &str, sx: f64, sy: f64, light: i32) {
+    let Some(lump) = game_state.sprites.frame_lump(sprite, 'A') else { return; };
+    let Some(texture) = game_state.textures.get(&lump.texture) else { return; };
+    let scale = screen.height as f64 / BASE_H;
+    let (w, h) = (screen.width as i32, screen.height as i32);
+    // Centrada en horizontal para que en pantallas anchas no quede corrida
+    let x1 = w as f64 / 2.0 + (sx - texture.left_offset as f64 - BASE_W / 2.0) * scale;
+    let y1 = (sy - texture.top_offset as f64) * scale;
+    let x_start = (x1.ceil() as i32).max(0);
+    let x_end = ((x1 + texture.width as f64 * scale).ceil() as i32 - 1).min(w - 1);
+    let y_start = (y1.ceil() as i32).max(0);
+    let y_end = ((y1 + texture.height as f64 * scale).ceil() as i32 - 1).min(h - 1);
+    for x in x_start..=x_end {
+        let mut tc = (((x as f64 + 0.5 - x1) / scale) as usize).min(texture.width - 1);
+        if lump.flip {
+            tc = texture.width - 1 - tc;
+        }
+        let column = texture.column(tc);
+        for y in y_start..=y_end {
+            let v = (((y as f64 + 0.5 - y1) / scale) as usize).min(texture.height - 1);
+            let color = column[v];
+            if color != textures::TRANSPARENT {
+                screen.plot(y as usize * w as usize + x as usize, color, light);
+            }
+        }
+    }
+}
+
+// Arma en primera persona: se balancea al caminar, sube al cambiarla y se ilumina con el fogonazo
+pub fn draw_weapon(screen: &mut Screen, game_state: &GameStateT, player: &PlayerT) {
+    if player::is_dead(player) {
+        return;
+    }
+    let arsenal = &player.weapons;
+    let info = &WEAPONS[arsenal.current];
+    // Como en Doom: vaiven horizontal completo y vertical siempre hacia abajo
+    let angle = game_state.tic as f64 * TAU / BOB_PERIOD;
+    let sx = 1.0 + player.bob * angle.cos();
+    let sy = WEAPON_TOP + player.bob * (angle % PI).sin()
+        + (WEAPON_BOTTOM - WEAPON_TOP) * arsenal.raise as f64 / weapons::SWITCH_TICS as f64;
+    let is_flashing = arsenal.flash > 0;
+    let light = match game_state.sector_at(player.position.x, player.position.y) {
+        Some(sector) if !is_flashing => game_state.sectors[sector].light,
+        _ => 255,
+    };
+    draw_psprite(screen, game_state, info.sprite, sx, sy, light);
+    if let Some(flash) = info.flash && is_flashing {
+        draw_psprite(screen, game_state, flash, sx, sy, 255);
+    }
+}
+
+// Un valor del HUD: el numero grande y la etiqueta abajo, alineados en x
+fn draw_counter(screen: &mut Screen, font: &FontT, x: i32, label: &str, value: &str, color: u32, align: AlignT) {
+    let u = unit(screen);
+    let label_y = screen.height as i32 - MARGIN * u - font.line_height(LABEL_SCALE * u);
+    let number_y = label_y - font.line_height(NUMBER_SCALE * u);
+    text::draw_shadowed(screen, font, x, number_y, value, &TextStyleT::new(color, NUMBER_SCALE * u).aligned(align));
+    text::draw_shadowed(screen, font, x, label_y, label, &TextStyleT::new(LABEL_CLR, LABEL_SCALE * u).aligned(align));
+}
+
+// HUD de pantalla completa: salud y armadura abajo a la izquierda, llaves al lado y municion a la derecha
+pub fn draw_status(screen: &mut Screen, game_state: &GameStateT, player: &PlayerT) {
+    let font = &game_state.font;
+    let u = unit(screen);
+    let (w, h) = (screen.width as i32, screen.height as i32);
+    let column = text::text_width(font, "100%", NUMBER_SCALE * u) + MARGIN * 2 * u;
+
+    draw_counter(screen, font, MARGIN * u, "HEALTH", &format!("{}%", player.health), NUMBER_CLR, AlignT::Left);
+    draw_counter(screen, font, MARGIN * u + column, "ARMOR", &format!("{}%", player.armor), NUMBER_CLR, AlignT::Left);
+
+    // Llaves: un cuadrado por cada una que se tiene, apiladas de abajo hacia arriba
+    let key_x = MARGIN * u + column * 2;
+    for (index, _) in player.keys.iter().enumerate().filter(|(_, has)| **has) {
+        let y = h - (MARGIN + (KEY_SIZE + 2) * (index as i32 + 1)) * u;
+        screen.blend_rect(key_x, y, KEY_SIZE * u, KEY_SIZE * u, KEY_COLORS[index], 255);
+    }
+
+    // Municion del arma actual (el puño no usa)
+    let arsenal = &player.weapons;
+    let info = &WEAPONS[arsenal.current];
+    let (value, color) = match info.ammo {
+        Some(ammo) => {
+            let count = arsenal.ammo[ammo as usize];
+            let low = count < weapons::MAX_AMMO[ammo as usize] / 8;
+            (count.to_string(), if low { LOW_AMMO_CLR } else { NUMBER_CLR })
+        }
+        None => ("-".to_string(), NUMBER_CLR),
+    };
+    draw_counter(screen, font, w - MARGIN * u, info.name, &value, color, AlignT::Right);
+
+    let hud = &game_state.hud;
+    if !hud.message.is_empty() && game_state.tic < hud.message_tic + MESSAGE_TICS {
+        text::draw_shadowed(screen, font, MARGIN * u, MARGIN * u, &hud.message, &TextStyleT::new(MESSAGE_CLR, MESSAGE_SCALE * u));
+    }
+
+    if player::is_dead(player) && player.dead_tics >= player::RESPAWN_TICS {
+        let style = TextStyleT::new(LABEL_CLR, NUMBER_SCALE * u / 2 + 1).aligned(AlignT::Center);
+        text::draw_shadowed(screen, font, w / 2, h / 2, "PRESS FIRE TO RESTART", &style);
+    }
+}
@@ -0,0 +1,207 @@
+use std::{fs, path::Path};
+use crate::{cli, demo::{self, DemoModeT}, entities::TallyT, game_state::{self, GameStateT, TICRATE}, hud, menu::{self, GameModeT}, player::{self, PlayerT}, renderer::{SectorT, Screen}, text::{self, AlignT, TextStyleT}, wad::WadT};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const EPISODE_FILE: &str = "maps/episode.txt"; // Orden de los mapas si no se pide otro archivo
+pub const BACKGROUND_ALPHA: u8 = 224;
+pub const TITLE_SCALE: i32 = 3;
+pub const STAT_SCALE: i32 = 2;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Secretos y comienzo del nivel en curso (las muertes y los items los lleva EntitiesT)
+#[derive(Clone)]
+pub struct LevelStatsT {
+    pub secrets: i32,
+    pub total_secrets: i32,
+    pub start_tic: u64, // Tic en que empezo el nivel
+}
+    impl LevelStatsT {
+        pub fn new() -> Self {
+            LevelStatsT { secrets: 0, total_secrets: 0, start_tic: 0 }
+        }
+    }
+
+// Resumen del nivel terminado que muestra la pantalla de intermedio
+pub struct IntermissionT {
+    pub map_name: String,
+    pub tally: TallyT,
+    pub stats: LevelStatsT,
+    pub tics: u64,            // Lo que duro el nivel
+    pub next: Option<String>, // Mapa que sigue (None = fin del episodio)
+}
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+// Nombre de un mapa para mostrar: el del archivo sin la extension
+pub fn map_name(path: &str) -> String {
+    Path::new(path).file_stem().map_or(path.to_string(), |stem| stem.to_string_lossy().to_uppercase())
+}
+
+// Contadores en cero con los secretos del mapa recien cargado
+pub fn count_level(sectors: &[SectorT], tic: u64) -> LevelStatsT {
+    let mut stats = LevelStatsT::new();
+    stats.total_secrets = sectors.iter().filter(|s| s.is_secret).count() as i32;
+    stats.start_tic = tic;
+    stats
+}
+
+// Contadores del nivel en una linea (overlay de debug y automapa)
+pub fn summary(state: &GameStateT) -> String {
+    let (tally, stats) = (&state.entities.tally, &state.stats);
+    format!("KILLS {}/{}  ITEMS {}/{}  SECRETS {}/{}", tally.kills, tally.total_kills, tally.items, tally.total_items,
+        stats.secrets, stats.total_secrets)
+}
+
+// Lista de mapas, uno por linea (nombre en maps/ o ruta); '#' empieza un comentario
+pub fn parse_episode(source: &str, wad: Option<&WadT>) -> Result<Vec<String>, String> {
+    let mut maps = Vec::new();
+    for (line_num, line) in source.lines().enumerate() {
+        let name = line.split('#').next().unwrap_or("").trim();
+        if name.is_empty() {
+            continue;
+        }
+        maps.push(cli::resolve_map(name, wad).map_err(|e| format!("linea {}: {}", line_num + 1, e))?);
+    }
+    if maps.is_empty() {
+        return Err("el episodio no tiene mapas".to_string());
+    }
+    Ok(maps)
+}
+
+pub fn load_episode(path: &str, wad: Option<&WadT>) -> Result<Vec<String>, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_episode(&source, wad).map_err(|e| format!("{}: {}", path, e))
+}
+
+// Sin archivo de episodio: los niveles del WAD en su orden, los que tengan su version en maps/
+pub fn wad_episode(wad: &WadT) -> Vec<String> {
+    wad.map_names().iter().filter_map(|name| cli::resolve_map(&name.to_ascii_lowercase(), None).ok()).collect()
+}
+
+// Orden de los mapas: el archivo pedido (tiene que cargar), el de por defecto o los nombres del WAD
+pub fn episode(path: Option<&str>, wad: Option<&WadT>) -> Result<Vec<String>, String> {
+    if let Some(path) = path {
+        return load_episode(path, wad);
+    }
+    if Path::new(EPISODE_FILE).is_file() {
+        return load_episode(EPISODE_FILE, wad);
+    }
+    Ok(wad.map(wad_episode).unwrap_or_default())
+}
+
+// Primer mapa de una partida nueva
+pub fn first_map(state: &GameStateT) -> String {
+    match state.episode.first() {
+        Some(path) => path.clone(),
+        None if state.map_path.is_empty() => game_state::DEFAULT_MAP.to_string(),
+        None => state.map_path.clone(),
+    }
+}
+
+// El que sigue al mapa actual en el episodio (fuera del episodio no hay siguiente)
+pub fn next_map(state: &GameStateT) -> Option<String> {
+    let current = state.episode.iter().position(|path| *path == state.map_path)?;
+    state.episode.get(current + 1).cloned()
+}
+
+// Salida del nivel: congela los contadores y pasa a la pantalla de intermedio
+pub fn finish_level(state: &mut GameStateT) {
+    if state.mode == GameModeT::Intermission {
+        return;
+    }
+    state.intermission = Some(IntermissionT {
+        map_name: map_name(&state.map_path),
+        tally: state.entities.tally,
+        stats: state.stats.clone(),
+        tics: state.tic - state.stats.start_tic,
+        next: next_map(state),
+    });
+    // Una demo cubre un solo mapa: termina con el nivel
+    if let Some(demo) = &mut state.demo {
+        match demo.mode {
+            DemoModeT::Playing => demo.position = demo.frames.len(),
+            DemoModeT::Recording => {
+                match demo::save(demo) {
+                    Ok(()) => println!("Demo guardada: {} ({} frames)", demo.path, demo.frames.len()),
+                    Err(e) => eprintln!("Error al guardar la demo: {}", e),
+                }
+                state.demo = None;
+            }
+        }
+    }
+    menu::set_mode(state, GameModeT::Intermission);
+}
+
+// Una tecla en el intermedio: el mapa siguiente con lo que lleva el jugador, o el menu al terminar el episodio
+pub fn advance(state: &mut GameStateT, player: &mut PlayerT) {
+    // Sin resumen (se cargo otro mapa desde la consola) se sigue jugando
+    let Some(intermission) = state.intermission.take() else {
+        menu::set_mode(state, GameModeT::Playing);
+        return;
+    };
+    let Some(next) = intermission.next else {
+        menu::set_mode(state, GameModeT::MainMenu);
+        return;
+    };
+    player::finish_level(player);
+    match game_state::load_level(state, player, &next) {
+        Ok(()) => menu::set_mode(state, GameModeT::Playing),
+        Err(e) => {
+            state.console.print(&e);
+            menu::set_mode(state, GameModeT::MainMenu);
+        }
+    }
+}
+
+// Porcentaje de un contador (sin nada que contar queda en 0, como en Doom)
+fn percent(count: i32, total: i32) -> i32 {
+    count * 100 / total.max(1)
+}
+
+fn format_time(tics: u64) -> String {
+    let seconds = (tics as f64 / TICRATE) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// Pantalla de intermedio: contadores del nivel, tiempo y el mapa que sigue
+pub fn draw(screen: &mut Screen, state: &GameStateT) {
+    let Some(intermission) = &state.intermission else { return; };
+    let (w, h) = (screen.width as i32, screen.height as i32);
+    screen.blend_rect(0, 0, w, h, menu::DIM_CLR, BACKGROUND_ALPHA);
+    let unit = (h / 240).max(1);
+    let font = &state.font;
+    let title = TextStyleT::new(menu::TITLE_CLR, TITLE_SCALE * unit).aligned(AlignT::Center);
+    let label = TextStyleT::new(hud::LABEL_CLR, STAT_SCALE * unit);
+    let value = TextStyleT::new(hud::NUMBER_CLR, STAT_SCALE * unit).aligned(AlignT::Right);
+    let title_h = font.line_height(TITLE_SCALE * unit);
+    let line_h = font.line_height(STAT_SCALE * unit) * 3 / 2;
+
+    let mut y = h / 6;
+    text::draw_text(screen, font, w / 2, y, &format!("{} FINISHED", intermission.map_name), &title);
+    y += title_h * 2;
+
+    let (tally, stats) = (&intermission.tally, &intermission.stats);
+    let rows = [
+        ("KILLS", format!("{}%", percent(tally.kills, tally.total_kills))),
+        ("ITEMS", format!("{}%", percent(tally.items, tally.total_items))),
+        ("SECRET", format!("{}%", percent(stats.secrets, stats.total_secrets))),
+        ("TIME", format_time(intermission.tics)),
+    ];
+    let (left, right) = (w / 4, w * 3 / 4);
+    for (name, text) in rows {
+        text::draw_text(screen, font, left, y, name, &label);
+        text::draw_text(screen, font, right, y, &text, &value);
+        y += line_h;
+    }
+
+    y += line_h;
+    match &intermission.next {
+        Some(next) => {
+            text::draw_text(screen, font, w / 2, y, "ENTERING", &title);
+            text::draw_text(screen, font, w / 2, y + title_h, &map_name(next), &title);
+        }
+        None => text::draw_text(screen, font, w / 2, y, "EPISODE COMPLETE", &title),
+    }
+    let hint = TextStyleT::new(menu::HINT_CLR, STAT_SCALE * unit).aligned(AlignT::Center);
+    text::draw_text(screen, font, w / 2, h - line_h * 2, "PRESS ANY KEY", &hint);
+}
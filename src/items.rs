@@ -0,0 +1,168 @@
+use crate::{collision, entities::{EntityIdT, ThingKindT}, game_state::{GameStateT, TICRATE}, hud, player::{self, PlayerT}, sprites::SpriteThingT, weapons::{self, AmmoT}};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const BONUS_TICS: i32 = 6;     // Destello por cada item levantado (como BONUSADD)
+pub const MAX_BONUS_HEALTH: i32 = 200;
+pub const MAX_ARMOR: i32 = 200;
+const REACH_BELOW: f64 = 8.0;      // Un item un poco por debajo de los pies tambien se alcanza
+
+// Items por sprite (cantidades y mensajes de Doom)
+pub static ITEMS: [ItemInfoT; 22] = [
+    ItemInfoT { sprite: "STIM", effect: EffectT::Health(10, player::MAX_HEALTH), message: "PICKED UP A STIMPACK." },
+    ItemInfoT { sprite: "MEDI", effect: EffectT::Health(25, player::MAX_HEALTH), message: "PICKED UP A MEDIKIT." },
+    ItemInfoT { sprite: "BON1", effect: EffectT::Health(1, MAX_BONUS_HEALTH), message: "PICKED UP A HEALTH BONUS." },
+    ItemInfoT { sprite: "SOUL", effect: EffectT::Health(100, MAX_BONUS_HEALTH), message: "SUPERCHARGE!" },
+    ItemInfoT { sprite: "ARM1", effect: EffectT::Armor(100, 1), message: "PICKED UP THE ARMOR." },
+    ItemInfoT { sprite: "ARM2", effect: EffectT::Armor(200, 2), message: "PICKED UP THE MEGAARMOR!" },
+    ItemInfoT { sprite: "BON2", effect: EffectT::ArmorBonus(1), message: "PICKED UP AN ARMOR BONUS." },
+    ItemInfoT { sprite: "CLIP", effect: EffectT::Ammo(AmmoT::Bullets, 10), message: "PICKED UP A CLIP." },
+    ItemInfoT { sprite: "AMMO", effect: EffectT::Ammo(AmmoT::Bullets, 50), message: "PICKED UP A BOX OF BULLETS." },
+    ItemInfoT { sprite: "SHEL", effect: EffectT::Ammo(AmmoT::Shells, 4), message: "PICKED UP 4 SHOTGUN SHELLS." },
+    ItemInfoT { sprite: "SBOX", effect: EffectT::Ammo(AmmoT::Shells, 20), message: "PICKED UP A BOX OF SHOTGUN SHELLS." },
+    ItemInfoT { sprite: "ROCK", effect: EffectT::Ammo(AmmoT::Rockets, 1), message: "PICKED UP A ROCKET." },
+    ItemInfoT { sprite: "BROK", effect: EffectT::Ammo(AmmoT::Rockets, 5), message: "PICKED UP A BOX OF ROCKETS." },
+    ItemInfoT { sprite: "CELL", effect: EffectT::Ammo(AmmoT::Cells, 20), message: "PICKED UP AN ENERGY CELL." },
+    ItemInfoT { sprite: "CELP", effect: EffectT::Ammo(AmmoT::Cells, 100), message: "PICKED UP AN ENERGY CELL PACK." },
+    ItemInfoT { sprite: "SHOT", effect: EffectT::Weapon(2, 8), message: "YOU GOT THE SHOTGUN!" },
+    ItemInfoT { sprite: "MGUN", effect: EffectT::Weapon(3, 20), message: "YOU GOT THE CHAINGUN!" },
+    ItemInfoT { sprite: "LAUN", effect: EffectT::Weapon(4, 2), message: "YOU GOT THE ROCKET LAUNCHER!" },
+    ItemInfoT { sprite: "PLAS", effect: EffectT::Weapon(5, 40), message: "YOU GOT THE PLASMA GUN!" },
+    ItemInfoT { sprite: "BKEY", effect: EffectT::Key(0), message: "PICKED UP A BLUE KEYCARD." },
+    ItemInfoT { sprite: "YKEY", effect: EffectT::Key(1), message: "PICKED UP A YELLOW KEYCARD." },
+    ItemInfoT { sprite: "RKEY", effect: EffectT::Key(2), message: "PICKED UP A RED KEYCARD." },
+];
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy)]
+pub enum EffectT {
+    Health(i32, i32),   // Cantidad y maximo al que llega
+    Armor(i32, i32),    // Puntos y tipo (solo si es mejor que la que se tiene)
+    ArmorBonus(i32),    // Suma hasta MAX_ARMOR
+    Ammo(AmmoT, i32),
+    Weapon(usize, i32), // Arma y municion que trae
+    Key(usize),
+}
+
+pub struct ItemInfoT {
+    pub sprite: &'static str,
+    pub effect: EffectT,
+    pub message: &'static str,
+}
+
+// Item levantado que vuelve a aparecer en su lugar (solo con item_respawn)
+#[derive(Clone)]
+pub struct RespawnT {
+    pub thing: SpriteThingT,
+    pub tic: u64,
+}
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+pub fn item_info(sprite: &str) -> Option<&'static ItemInfoT> {
+    ITEMS.iter().find(|item| item.sprite == sprite)
+}
+
+// Aplica el item respetando los topes; false si no le sirve al jugador (y queda en el piso)
+pub fn apply(player: &mut PlayerT, effect: EffectT) -> bool {
+    match effect {
+        EffectT::Health(amount, max) => {
+            if player.health >= max {
+                return false;
+            }
+            player.health = (player.health + amount).min(max);
+        }
+        EffectT::Armor(points, armor_type) => {
+            if player.armor >= points {
+                return false;
+            }
+            player.armor = points;
+            player.armor_type = armor_type;
+        }
+        EffectT::ArmorBonus(points) => {
+            if player.armor >= MAX_ARMOR {
+                return false;
+            }
+            player.armor = (player.armor + points).min(MAX_ARMOR);
+            if player.armor_type == 0 {
+                player.armor_type = 1;
+            }
+        }
+        EffectT::Ammo(ammo, amount) => return give_ammo(&mut player.weapons, ammo, amount),
+        EffectT::Weapon(weapon, amount) => {
+            let arsenal = &mut player.weapons;
+            let is_new = !arsenal.owned[weapon];
+            let got_ammo = weapons::WEAPONS[weapon].ammo.is_some_and(|ammo| give_ammo(arsenal, ammo, amount));
+            if !is_new && !got_ammo {
+                return false;
+            }
+            arsenal.owned[weapon] = true;
+            // Como en Doom se saca el arma nueva
+            if is_new {
+                weapons::select(arsenal, weapon);
+            }
+        }
+        EffectT::Key(key) => {
+            if player.keys[key] {
+                return false;
+            }
+            player.keys[key] = true;
+        }
+    }
+    true
+}
+
+fn give_ammo(arsenal: &mut weapons::ArsenalT, ammo: AmmoT, amount: i32) -> bool {
+    let (count, max) = (&mut arsenal.ammo[ammo as usize], weapons::MAX_AMMO[ammo as usize]);
+    if *count >= max {
+        return false;
+    }
+    *count = (*count + amount).min(max);
+    true
+}
+
+// Levanta los items que toca el jugador en su posicion actual
+pub fn touch_items(state: &mut GameStateT, player: &mut PlayerT) {
+    let Some(sector) = state.sector_at(player.position.x, player.position.y) else { return; };
+    let near = collision::nearby_sectors(&state.sectors, sector);
+
+    let feet = player.z - player::EYE_HEIGHT;
+    let touched: Vec<(EntityIdT, &'static ItemInfoT)> = near.iter().flat_map(|&s| state.entities.in_sector(s))
+        .filter(|e| e.kind == ThingKindT::Item)
+        .filter(|e| {
+            let reach = e.radius + collision::PLAYER_RADIUS;
+            let (dx, dy) = ((e.thing.position.x - player.position.x).abs(), (e.thing.position.y - player.position.y).abs());
+            let dz = e.thing.z - feet;
+            dx < reach && dy < reach && (-REACH_BELOW..=collision::PLAYER_HEIGHT).contains(&dz)
+        })
+        .filter_map(|e| item_info(&e.thing.sprite).map(|info| (e.id, info)))
+        .collect();
+
+    for (id, info) in touched {
+        if !apply(player, info.effect) {
+            continue;
+        }
+        player.bonus_count = (player.bonus_count + BONUS_TICS).min(100);
+        hud::show_message(&mut state.hud, state.tic, info.message);
+        // Con reaparicion (partidas en red) las llaves quedan para los demas
+        let respawns = state.item_respawn > 0.0;
+        if respawns && matches!(info.effect, EffectT::Key(_)) {
+            continue;
+        }
+        let Some(entity) = state.entities.pick_up(id) else { continue; };
+        if respawns {
+            let tic = state.tic + (state.item_respawn * TICRATE) as u64;
+            state.respawns.push(RespawnT { thing: entity.thing, tic });
+        }
+    }
+}
+
+// Vuelve a poner los items levantados a los que ya les toco
+pub fn tick(state: &mut GameStateT) {
+    let (due, waiting): (Vec<RespawnT>, Vec<RespawnT>) = state.respawns.drain(..).partition(|r| r.tic <= state.tic);
+    state.respawns = waiting;
+    for respawn in due {
+        if let Some(sector) = state.sector_at(respawn.thing.position.x, respawn.thing.position.y) {
+            state.entities.spawn(respawn.thing, sector);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use core::f64;
-use sdl2::{event::Event, keyboard::Scancode, EventPump};
-use crate::{game_state, player};
+use sdl2::{event::Event, keyboard::Scancode, mouse::MouseButton, EventPump};
+use crate::{automap, collision::{self, BodyT}, console, demo, game_state, items, menu::{self, GameModeT}, player, savegame, specials, typedefs::Vec2T, weapons};
+
+// Radianes por pixel de mouse con sensibilidad 1
+pub const MOUSE_SCALE: f64 = 0.0005;
 
 // Velocidades predeterminadas
 pub struct SpeedT {
@@ -12,24 +15,82 @@ impl SpeedT {
     pub fn new() -> Self {
         SpeedT { 
             mov_speed: 150.0,
-            elevation_speed: (500*100) as f64,
+            elevation_speed: 64.0,
             rot_speed: 4.0,
         }
     }
 }
+
+// Acceso por nombre a las teclas de KeymapT (el nombre es el del campo), generado de una sola lista
+macro_rules! actions {
+    ($($action:ident),* $(,)?) => {
+        // Acciones que se pueden reasignar (en el orden en que se guardan)
+        pub const ACTIONS: &[&str] = &[$(stringify!($action)),*];
+
+        impl KeymapT {
+            pub fn binding_mut(&mut self, action: &str) -> Option<&mut Scancode> {
+                match action {
+                    $(stringify!($action) => Some(&mut self.$action),)*
+                    _ => None,
+                }
+            }
+
+            // Sin pedir el keymap mutable (para mostrarla)
+            pub fn binding(&self, action: &str) -> Option<Scancode> {
+                match action {
+                    $(stringify!($action) => Some(self.$action),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+actions!(
+    forward, backward, left, right, menu, strafe_left, strafe_right, up, down,
+    toggle_map, debug_mode, toggle_palette, map_zoom_in, map_zoom_out, map_follow,
+    map_overlay, map_reveal, debug_clip, console, map_pan_up, map_pan_down,
+    map_pan_left, map_pan_right, quicksave, quickload, fire, next_weapon, prev_weapon,
+    activate,
+);
+
+// Teclas fijas de las armas, como en Doom (1 = puño ... 6 = plasma)
+pub const WEAPON_KEYS: [Scancode; weapons::NUM_WEAPONS] = [
+    Scancode::Num1, Scancode::Num2, Scancode::Num3, Scancode::Num4, Scancode::Num5, Scancode::Num6,
+];
+
 ///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone)]
 pub struct KeymapT {
     pub forward: Scancode,
     pub backward: Scancode,
     pub left: Scancode,
     pub right: Scancode,
-    pub quit: Scancode,
+    pub menu: Scancode,
     pub strafe_left: Scancode,
     pub strafe_right: Scancode,
     pub up: Scancode,
     pub down: Scancode,
     pub toggle_map: Scancode,
     pub debug_mode: Scancode,
+    pub toggle_palette: Scancode,
+    pub map_zoom_in: Scancode,
+    pub map_zoom_out: Scancode,
+    pub map_follow: Scancode,
+    pub map_overlay: Scancode,
+    pub map_reveal: Scancode,
+    pub debug_clip: Scancode,
+    pub console: Scancode,
+    pub map_pan_up: Scancode,
+    pub map_pan_down: Scancode,
+    pub map_pan_left: Scancode,
+    pub map_pan_right: Scancode,
+    pub quicksave: Scancode,
+    pub quickload: Scancode,
+    pub fire: Scancode, // El boton izquierdo del mouse tambien dispara
+    pub next_weapon: Scancode,
+    pub prev_weapon: Scancode,
+    pub activate: Scancode, // Usar: puertas, ascensores e interruptores
 }
     impl KeymapT {
         pub fn new() -> Self {
@@ -38,15 +99,37 @@ pub struct KeymapT {
                 backward: Scancode::S,
                 left: Scancode::A,
                 right: Scancode::D,
-                quit: Scancode::Escape,
+                menu: Scancode::Escape,
                 strafe_left: Scancode::Q,
                 strafe_right: Scancode::E,
                 up: Scancode::Space,
                 down: Scancode::LCtrl,
                 toggle_map: Scancode::M,
                 debug_mode: Scancode::O,
+                toggle_palette: Scancode::P,
+                map_zoom_in: Scancode::Equals,
+                map_zoom_out: Scancode::Minus,
+                map_follow: Scancode::F,
+                map_overlay: Scancode::Tab,
+                map_reveal: Scancode::R,
+                debug_clip: Scancode::K,
+                console: Scancode::Grave,
+                map_pan_up: Scancode::Up,
+                map_pan_down: Scancode::Down,
+                map_pan_left: Scancode::Left,
+                map_pan_right: Scancode::Right,
+                quicksave: Scancode::F6,
+                quickload: Scancode::F9,
+                fire: Scancode::RCtrl,
+                next_weapon: Scancode::RightBracket,
+                prev_weapon: Scancode::LeftBracket,
+                activate: Scancode::Return,
             }
         }
+
+        pub fn bindings(&self) -> Vec<(&'static str, Scancode)> {
+            ACTIONS.iter().filter_map(|&action| self.binding(action).map(|s| (action, s))).collect()
+        }
     }
 
 pub struct KeystatesT {
@@ -59,7 +142,16 @@ pub struct KeystatesT {
     pub up: bool,
     pub down: bool,
     pub map_state: bool,
-    pub is_debug: bool,
+    pub map_zoom_in: bool,
+    pub map_zoom_out: bool,
+    pub map_pan_up: bool,
+    pub map_pan_down: bool,
+    pub map_pan_left: bool,
+    pub map_pan_right: bool,
+    pub mouse_turn: f64, // Giro acumulado del mouse en el frame (radianes)
+    pub fire: bool,
+    pub weapon_select: Option<usize>, // Arma pedida en este frame
+    pub activate: bool, // Se apreto usar en este frame
 }
     impl KeystatesT {
         pub fn new() -> Self {
@@ -73,7 +165,16 @@ pub struct KeystatesT {
                 up: false,
                 down: false,
                 map_state: false,
-                is_debug: false,
+                map_zoom_in: false,
+                map_zoom_out: false,
+                map_pan_up: false,
+                map_pan_down: false,
+                map_pan_left: false,
+                map_pan_right: false,
+                mouse_turn: 0.0,
+                fire: false,
+                weapon_select: None,
+                activate: false,
             }
         }
     }
@@ -91,9 +192,9 @@ pub enum KbdKeyState {
 pub fn process_keystates( 
     keystates: &mut KeystatesT,
     player: &mut player::PlayerT,
+    speed_config: &SpeedT,
     delta_time: f64
 ) {
-    let speed_config: SpeedT = SpeedT::new();
 
     // Movimientos hacia adelante (+) y atrás (-)
     if keystates.forward {
@@ -106,6 +207,8 @@ pub fn process_keystates(
     }
 
     // Movimientos hacia izquierda (giro +) y derecha (giro -)
+    player.dir_angle -= keystates.mouse_turn;
+    keystates.mouse_turn = 0.0;
     if keystates.left {
         player.dir_angle += speed_config.rot_speed * delta_time;
     }
@@ -123,7 +226,10 @@ pub fn process_keystates(
         player.position.y -= speed_config.mov_speed * f64::sin(player.dir_angle + f64::consts::PI/2.0) * delta_time;
     }
 
-    // Movimientos hacia arriba (+) y abajo (-)
+    // Movimientos hacia arriba (+) y abajo (-): solo volando, si no manda la gravedad
+    if !player.is_flying && !player.is_noclip {
+        return;
+    }
     if keystates.up {
         player.z += speed_config.elevation_speed * delta_time;
     }
@@ -163,9 +269,36 @@ pub fn handle_realtimekeys(
         keystates.down = state == KbdKeyState::Down;
     }
 
-    if *key_scancode == keymap.debug_mode && state == KbdKeyState::Down {
-        keystates.is_debug = !keystates.is_debug;
+    // Automapa
+    if *key_scancode == keymap.toggle_map && state == KbdKeyState::Down {
+        keystates.map_state = !keystates.map_state;
+    }
+    if *key_scancode == keymap.map_zoom_in {
+        keystates.map_zoom_in = state == KbdKeyState::Down;
+    } else if *key_scancode == keymap.map_zoom_out {
+        keystates.map_zoom_out = state == KbdKeyState::Down;
+    }
+    if *key_scancode == keymap.map_pan_up {
+        keystates.map_pan_up = state == KbdKeyState::Down;
+    } else if *key_scancode == keymap.map_pan_down {
+        keystates.map_pan_down = state == KbdKeyState::Down;
     }
+    if *key_scancode == keymap.map_pan_left {
+        keystates.map_pan_left = state == KbdKeyState::Down;
+    } else if *key_scancode == keymap.map_pan_right {
+        keystates.map_pan_right = state == KbdKeyState::Down;
+    }
+
+    if *key_scancode == keymap.fire {
+        keystates.fire = state == KbdKeyState::Down;
+    }
+}
+
+// Suelta todas las teclas mantenidas (al abrir la consola no tienen que quedar trabadas)
+fn release_keys(keystates: &mut KeystatesT) {
+    let map_state = keystates.map_state;
+    *keystates = KeystatesT::new();
+    keystates.map_state = map_state;
 }
 
 // Manejar Eventos del teclado
@@ -179,32 +312,185 @@ pub fn handle_events(
     for event in event_pump.poll_iter() {
         match event {
             // Evento KeyDown
-            Event::KeyDown { scancode: Some(scancode), .. } => {
+            Event::KeyDown { scancode: Some(scancode), repeat, .. } => {
+                // La tecla que quedo apretada al salir del nivel no se saltea el intermedio
+                if repeat && game_state.mode == GameModeT::Intermission {
+                    continue;
+                }
+                // Esperando la tecla nueva de un control: la recibe el menu aunque sea la de la consola
+                if game_state.menu.rebinding.is_some() {
+                    menu::handle_key(scancode, keymap, game_state, player);
+                    continue;
+                }
+                // La consola se queda con el teclado mientras esta abierta
+                if scancode == keymap.console {
+                    console::toggle(&mut game_state.console);
+                    release_keys(keystates);
+                    continue;
+                }
+                if game_state.console.is_open {
+                    console::handle_key(scancode, game_state, player);
+                    continue;
+                }
+                // Fuera del juego las teclas van a los menus
+                if game_state.mode != GameModeT::Playing {
+                    menu::handle_key(scancode, keymap, game_state, player);
+                    continue;
+                }
+                if scancode == keymap.menu {
+                    menu::set_mode(game_state, GameModeT::Paused);
+                    release_keys(keystates);
+                    continue;
+                }
+
+                // Guardado y carga rapida (la partida cargada no hereda las teclas mantenidas)
+                if scancode == keymap.quicksave {
+                    match savegame::save_game(game_state, player, savegame::QUICKSAVE_SLOT) {
+                        Ok(path) => game_state.console.print(&format!("partida guardada: {}", path.display())),
+                        Err(e) => game_state.console.print(&e),
+                    }
+                    continue;
+                }
+                if scancode == keymap.quickload {
+                    match savegame::load_game(game_state, player, savegame::QUICKSAVE_SLOT) {
+                        Ok(()) => game_state.console.print("partida cargada"),
+                        Err(e) => game_state.console.print(&e),
+                    }
+                    release_keys(keystates);
+                    continue;
+                }
+
+                // Cambio de arma: se guarda el arma elegida (asi la demo la repite igual)
+                if let Some(weapon) = WEAPON_KEYS.iter().position(|&key| key == scancode) {
+                    keystates.weapon_select = Some(weapon);
+                } else if scancode == keymap.next_weapon {
+                    keystates.weapon_select = weapons::cycle(&player.weapons, 1);
+                } else if scancode == keymap.prev_weapon {
+                    keystates.weapon_select = weapons::cycle(&player.weapons, -1);
+                }
+                if scancode == keymap.activate {
+                    keystates.activate = true;
+                }
+
                 handle_realtimekeys(keymap, keystates,&scancode, KbdKeyState::Down);
                 game_state.state_show_map = keystates.map_state;
                 
-                if scancode == keymap.quit {
-                    game_state.is_running = false;
-                }
-
                 if scancode == keymap.debug_mode {
                     game_state.is_debug_mode = !game_state.is_debug_mode;
                 }
-                break;
+                if game_state.is_debug_mode && scancode == keymap.debug_clip {
+                    game_state.debug.show_clip = !game_state.debug.show_clip;
+                }
+
+                if scancode == keymap.toggle_palette {
+                    game_state.is_palette_mode = !game_state.is_palette_mode;
+                }
+
+                if game_state.state_show_map && scancode == keymap.map_follow {
+                    game_state.automap.follow = !game_state.automap.follow;
+                }
+                if game_state.state_show_map && scancode == keymap.map_overlay {
+                    game_state.automap.overlay = !game_state.automap.overlay;
+                }
+                // Revelar el mapa entero solo en modo debug (truco)
+                if game_state.state_show_map && game_state.is_debug_mode && scancode == keymap.map_reveal {
+                    game_state.automap.reveal_all = !game_state.automap.reveal_all;
+                }
             }
 
             // Evento KeyUp
             Event::KeyUp { scancode: Some(scancode), .. } => {
                 handle_realtimekeys(keymap, keystates,&scancode, KbdKeyState::Up);
-                break;
+            }
+
+            // Mouse: solo gira (el modo relativo lo activa main mientras se juega)
+            Event::MouseMotion { xrel, .. } if !game_state.is_paused && !game_state.console.is_open => {
+                keystates.mouse_turn += xrel as f64 * game_state.mouse_sensitivity * MOUSE_SCALE;
+            }
+
+            // Boton izquierdo dispara y la rueda cambia de arma
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, .. } if game_state.mode == GameModeT::Playing && !game_state.console.is_open => {
+                keystates.fire = true;
+            }
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, .. } => {
+                keystates.fire = false;
+            }
+            Event::MouseWheel { y, .. } if game_state.mode == GameModeT::Playing && !game_state.console.is_open && y != 0 => {
+                keystates.weapon_select = weapons::cycle(&player.weapons, y.signum());
+            }
+
+            Event::TextInput { text, .. } if game_state.console.is_open => {
+                console::type_text(&mut game_state.console, &text);
             }
 
             Event::Quit { .. } => {
                 game_state.is_running = false;
-                break;
             }
-            _ => {break;}
+            _ => {}
         }
     }
-    process_keystates(keystates, player, game_state.delta_time);
-}
\ No newline at end of file
+    update_player(keystates, game_state, player);
+}
+
+// Rehace el movimiento del frame deslizandose por paredes y things, y mantiene los pies sobre el piso
+fn collide_player(game_state: &game_state::GameStateT, player: &mut player::PlayerT, from: &Vec2T) {
+    let body = BodyT {
+        radius: collision::PLAYER_RADIUS,
+        height: collision::PLAYER_HEIGHT,
+        feet_z: player.z - player::EYE_HEIGHT,
+        ignore: None,
+    };
+    let delta = Vec2T::new(player.position.x - from.x, player.position.y - from.y);
+    player.position = collision::slide_move(&game_state.sectors, &game_state.entities, from, &delta, &body);
+    if let Some(sector) = game_state.sector_at(player.position.x, player.position.y) {
+        let sector = &game_state.sectors[sector];
+        let top = sector.ceil_z() - (collision::PLAYER_HEIGHT - player::EYE_HEIGHT);
+        player.z = player.z.min(top).max(sector.floor_z() + player::EYE_HEIGHT);
+    }
+}
+
+// Armas, movimiento y choques del jugador vivo
+fn move_player(keystates: &mut KeystatesT, game_state: &mut game_state::GameStateT, player: &mut player::PlayerT) {
+    player.weapons.is_firing = keystates.fire;
+    if let Some(weapon) = keystates.weapon_select.take() {
+        weapons::select(&mut player.weapons, weapon);
+    }
+    let from = player.position.clone();
+    process_keystates(keystates, player, &game_state.speed, game_state.delta_time);
+    if !player.is_noclip {
+        collide_player(game_state, player, &from);
+    }
+    specials::player_moved(game_state, player, &from);
+    if std::mem::take(&mut keystates.activate) {
+        specials::use_lines(game_state, player);
+    }
+    items::touch_items(game_state, player);
+    // Como en Doom el balanceo crece con el cuadrado de la velocidad (en unidades por tic)
+    let per_tic = (player.position.x - from.x).hypot(player.position.y - from.y) / (game_state.delta_time * game_state::TICRATE).max(1e-9);
+    player.bob = (per_tic * per_tic / 4.0).min(player::MAX_BOB);
+}
+
+// Aplica las teclas mantenidas (pasando antes por la demo que se graba o reproduce)
+pub fn update_player(keystates: &mut KeystatesT, game_state: &mut game_state::GameStateT, player: &mut player::PlayerT) {
+    if game_state.is_paused {
+        keystates.mouse_turn = 0.0;
+        return;
+    }
+    if let Some(demo) = &mut game_state.demo {
+        demo::update(demo, keystates, &mut game_state.delta_time);
+    }
+    // Muerto no se mueve: disparar vuelve a empezar el nivel
+    if player::is_dead(player) {
+        keystates.mouse_turn = 0.0;
+        player.bob = 0.0;
+        if keystates.fire && player.dead_tics >= player::RESPAWN_TICS
+            && let Err(e) = game_state::restart_level(game_state, player) {
+            game_state.console.print(&e);
+        }
+    } else {
+        move_player(keystates, game_state, player);
+    }
+    if game_state.state_show_map {
+        automap::process_input(&mut game_state.automap, keystates, player, game_state.delta_time);
+    }
+}
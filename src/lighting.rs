@@ -0,0 +1,199 @@
+use crate::{renderer::SectorT, utils::GameRngT};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+// Tiempos en tics (35 por segundo, igual que en Doom)
+pub const STROBE_BRIGHT_TIME: i32 = 5;
+pub const STROBE_FAST_DARK: i32 = 15;
+pub const STROBE_SLOW_DARK: i32 = 35;
+pub const FLICKER_MAX_TIME: i32 = 64;
+pub const FLICKER_MIN_TIME: i32 = 7;
+pub const GLOW_SPEED: i32 = 8;
+
+// Cuantas unidades de distancia hacen falta para bajar un nivel de luz
+pub const LIGHT_FALLOFF: f64 = 6.0;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LightEffectT {
+    None,
+    Flicker,      // Parpadeo aleatorio (luz rota)
+    FireFlicker,  // Variacion suave tipo fuego
+    StrobeFast,
+    StrobeSlow,
+    Glow,         // Oscila entre min y max
+    Transfer(usize), // Copia la luz de otro sector
+}
+
+#[derive(Clone)]
+pub struct LightFxT {
+    pub effect: LightEffectT,
+    pub in_sync: bool,
+    pub min_light: i32,
+    pub max_light: i32,
+    pub min_override: Option<i32>,
+    pub count: i32,
+    pub direction: i32,
+}
+    impl LightFxT {
+        pub fn new() -> Self {
+            LightFxT {
+                effect: LightEffectT::None,
+                in_sync: false,
+                min_light: 0,
+                max_light: 255,
+                min_override: None,
+                count: 0,
+                direction: -1,
+            }
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+
+// Traduce el nombre usado en el archivo de mapa a un efecto (y si va sincronizado)
+pub fn parse_effect(name: &str) -> Result<(LightEffectT, bool), String> {
+    match name {
+        "none" => Ok((LightEffectT::None, false)),
+        "flicker" => Ok((LightEffectT::Flicker, false)),
+        "fire" => Ok((LightEffectT::FireFlicker, false)),
+        "strobe_fast" => Ok((LightEffectT::StrobeFast, false)),
+        "strobe_slow" => Ok((LightEffectT::StrobeSlow, false)),
+        "strobe_fast_sync" => Ok((LightEffectT::StrobeFast, true)),
+        "strobe_slow_sync" => Ok((LightEffectT::StrobeSlow, true)),
+        "glow" => Ok((LightEffectT::Glow, false)),
+        _ => Err(format!("efecto de luz desconocido '{}'", name)),
+    }
+}
+
+// Nivel de luz mas bajo entre los sectores vecinos (por portales)
+pub fn lowest_neighbor_light(sectors: &[SectorT], index: usize) -> i32 {
+    let mut min = sectors[index].light;
+    for wall in &sectors[index].walls {
+        if let Some(n) = wall.neighbor {
+            min = min.min(sectors[n].light);
+        }
+    }
+    min
+}
+
+// Nivel de luz mas alto entre los sectores vecinos
+pub fn highest_neighbor_light(sectors: &[SectorT], index: usize) -> i32 {
+    let mut max = sectors[index].light;
+    for wall in &sectors[index].walls {
+        if let Some(n) = wall.neighbor {
+            max = max.max(sectors[n].light);
+        }
+    }
+    max
+}
+
+// Cambia la luz base de un sector (interruptores): los efectos siguen desde el nuevo nivel
+pub fn set_light(sector: &mut SectorT, level: i32) {
+    sector.light = level;
+    sector.light_fx.max_light = level;
+    sector.light_fx.min_light = sector.light_fx.min_light.min(level);
+}
+
+// Calcula los limites de cada efecto una vez cargado el mapa (necesita a los vecinos)
+pub fn init_sector_lights(sectors: &mut [SectorT], rng: &mut GameRngT) {
+    for i in 0..sectors.len() {
+        let lowest = lowest_neighbor_light(sectors, i);
+        let sector = &mut sectors[i];
+        let fx = &mut sector.light_fx;
+
+        fx.max_light = sector.light;
+        fx.min_light = fx.min_override.unwrap_or(lowest);
+
+        match fx.effect {
+            LightEffectT::Flicker => {
+                fx.count = rng.range(0, FLICKER_MAX_TIME) + 1;
+            }
+            LightEffectT::FireFlicker => {
+                fx.min_light = fx.min_override.unwrap_or(lowest + 16);
+                fx.count = 4;
+            }
+            LightEffectT::StrobeFast | LightEffectT::StrobeSlow => {
+                if fx.min_light == fx.max_light {
+                    fx.min_light = 0;
+                }
+                fx.count = if fx.in_sync { 1 } else { rng.range(0, 8) + 1 };
+            }
+            LightEffectT::Glow => {
+                fx.direction = -1;
+            }
+            LightEffectT::None | LightEffectT::Transfer(_) => {}
+        }
+    }
+}
+
+// Avanza un tic todos los efectos de luz (con el azar de la simulacion, asi las demos se repiten igual)
+pub fn update(sectors: &mut [SectorT], rng: &mut GameRngT) {
+    for sector in sectors.iter_mut() {
+        let fx = &mut sector.light_fx;
+        match fx.effect {
+            LightEffectT::Flicker => {
+                fx.count -= 1;
+                if fx.count > 0 { continue; }
+                if sector.light == fx.max_light {
+                    sector.light = fx.min_light;
+                    fx.count = rng.range(0, FLICKER_MIN_TIME + 1) + 1;
+                } else {
+                    sector.light = fx.max_light;
+                    fx.count = rng.range(0, FLICKER_MAX_TIME + 1) + 1;
+                }
+            }
+            LightEffectT::FireFlicker => {
+                fx.count -= 1;
+                if fx.count > 0 { continue; }
+                let amount = rng.range(0, 4) * 16;
+                sector.light = (fx.max_light - amount).max(fx.min_light);
+                fx.count = 4;
+            }
+            LightEffectT::StrobeFast | LightEffectT::StrobeSlow => {
+                fx.count -= 1;
+                if fx.count > 0 { continue; }
+                if sector.light == fx.min_light {
+                    sector.light = fx.max_light;
+                    fx.count = STROBE_BRIGHT_TIME;
+                } else {
+                    sector.light = fx.min_light;
+                    fx.count = if fx.effect == LightEffectT::StrobeFast { STROBE_FAST_DARK } else { STROBE_SLOW_DARK };
+                }
+            }
+            LightEffectT::Glow => {
+                sector.light += GLOW_SPEED * fx.direction;
+                if sector.light <= fx.min_light {
+                    sector.light = fx.min_light;
+                    fx.direction = 1;
+                } else if sector.light >= fx.max_light {
+                    sector.light = fx.max_light;
+                    fx.direction = -1;
+                }
+            }
+            LightEffectT::None | LightEffectT::Transfer(_) => {}
+        }
+    }
+
+    // Las transferencias van al final para copiar el valor ya actualizado
+    for i in 0..sectors.len() {
+        if let LightEffectT::Transfer(src) = sectors[i].light_fx.effect {
+            sectors[i].light = sectors[src].light;
+        }
+    }
+}
+
+// Luz efectiva a cierta distancia (se oscurece con la profundidad)
+pub fn light_at(light: i32, depth: f64) -> i32 {
+    let dimmed = light - (depth / LIGHT_FALLOFF) as i32;
+    dimmed.clamp(light / 4, 255).max(0)
+}
+
+// Escala un color 0xRRGGBB segun un nivel de luz (0..255)
+pub fn shade(color: u32, light: i32) -> u32 {
+    let l = light.clamp(0, 255) as u32;
+    let r = ((color >> 16) & 0xFF) * l / 255;
+    let g = ((color >> 8) & 0xFF) * l / 255;
+    let b = (color & 0xFF) * l / 255;
+    (r << 16) | (g << 8) | b
+}
@@ -1,10 +1,11 @@
-mod typedefs; mod player; mod game_state; mod keyboard; mod window; mod renderer; mod utils;
+mod typedefs; mod player; mod game_state; mod keyboard; mod window; mod renderer; mod utils; mod lighting; mod map; mod wad; mod palette; mod textures; mod sprites; mod automap; mod text; mod debug; mod console; mod menu; mod settings; mod cli; mod demo; mod savegame; mod entities; mod collision; mod ai; mod weapons; mod hud; mod items; mod movers; mod specials; mod intermission;
 use core::f64;
+use std::time::Instant;
 use player::PlayerT;
 use game_state::GameStateT;
+use cli::ArgsT;
 use keyboard::{KeymapT, KeystatesT};
-use renderer::{SectorT, WallT};
-use sdl2::{EventPump, Sdl, TimerSubsystem, VideoSubsystem};
+use sdl2::{video::FullscreenType, EventPump, Sdl, TimerSubsystem, VideoSubsystem};
 
 ///////////////////////////////// SDL Contextos /////////////////////////////////
 pub struct SdlContextWrapper {
@@ -31,39 +32,269 @@ impl SdlContextWrapper {
 }
 
 ///////////////////////////////// MAIN /////////////////////////////////
-fn game_loop(mut context: SdlContextWrapper, mut game_state: GameStateT, mut player: PlayerT, mut keymap: KeymapT, mut keystates: KeystatesT, w:u32, h:u32){
+// Si hay un WAD se usan su PLAYPAL y COLORMAP para el modo 8 bits
+fn load_palette(screen: &mut renderer::Screen, game_state: &GameStateT) {
+    if let Some(wad) = &game_state.wad {
+        match palette::PaletteT::from_wad(wad) {
+            Ok(pal) => screen.palette = Some(pal),
+            Err(e) => eprintln!("Usando la paleta generada: {}", e),
+        }
+    }
+}
+
+// Avanza el mundo los tics que le tocan al frame (ninguno en pausa o en los menus)
+fn simulate(game_state: &mut GameStateT, player: &mut PlayerT) -> u32 {
+    let tics = if game_state.is_paused { 0 } else { game_state::pending_tics(game_state) };
+    for _ in 0..tics {
+        game_state::tick(game_state, player);
+    }
+    tics
+}
+
+// Dibuja el frame en el framebuffer; devuelve el tiempo de render del mundo
+fn draw_frame(screen: &mut renderer::Screen, game_state: &mut GameStateT, player: &PlayerT, keymap: &KeymapT) -> f64 {
+    let render_timer = Instant::now();
+    screen.set_indexed(game_state.is_palette_mode);
+    screen.active_palette = palette::flash_palette(player.damage_count, player.bonus_count);
+    if !game_state.state_show_map || game_state.automap.overlay {
+        let info = renderer::render(screen, player, game_state);
+        game_state.automap.mark_seen(&info.drawn_walls);
+        debug::record_render(&mut game_state.debug, &info);
+        hud::draw_weapon(screen, game_state, player);
+        if game_state.is_debug_mode && game_state.debug.show_clip {
+            debug::draw_clip_windows(screen, &info);
+        }
+    }
+    if game_state.state_show_map {
+        automap::draw(screen, game_state, player);
+    }
+    hud::draw_status(screen, game_state, player);
+    let render_time = render_timer.elapsed().as_secs_f64();
+    if game_state.is_debug_mode {
+        debug::draw(screen, game_state, player);
+    }
+    menu::draw(screen, game_state, keymap);
+    console::update(&mut game_state.console, game_state.delta_time);
+    console::draw(screen, game_state);
+    render_time
+}
+
+// Corta la ejecucion al llegar al limite de frames o al terminar la demo (si se pidio)
+fn check_exit(game_state: &mut GameStateT, args: &ArgsT, frames: u64) {
+    if args.frame_limit().is_some_and(|limit| frames >= limit) {
+        game_state.is_running = false;
+    }
+    if game_state.demo.as_ref().is_some_and(|demo| demo.is_finished()) {
+        if args.headless || args.screenshot.is_some() {
+            game_state.is_running = false;
+        } else {
+            // En ventana el jugador sigue desde donde quedo la demo
+            game_state.console.print("fin de la demo");
+            game_state.demo = None;
+        }
+    }
+}
+
+// Captura pedida por la linea de comandos y demo grabada
+fn finish(screen: &mut renderer::Screen, game_state: &GameStateT, args: &ArgsT) {
+    if let Some(path) = &args.screenshot {
+        screen.resolve_palette();
+        match screen.save_bmp(path) {
+            Ok(()) => println!("Captura guardada: {}", path),
+            Err(e) => eprintln!("Error al guardar la captura: {}", e),
+        }
+    }
+    if let Some(demo) = game_state.demo.as_ref().filter(|demo| demo.mode == demo::DemoModeT::Recording) {
+        match demo::save(demo) {
+            Ok(()) => println!("Demo guardada: {} ({} frames)", demo.path, demo.frames.len()),
+            Err(e) => eprintln!("Error al guardar la demo: {}", e),
+        }
+    }
+}
+
+fn game_loop(mut context: SdlContextWrapper, mut game_state: GameStateT, mut player: PlayerT, mut keymap: KeymapT, mut keystates: KeystatesT, args: &ArgsT){
     let mut screen: renderer::Screen = renderer::Screen::new();
-    let mut canvas = screen.init_screen(&context.video_subsystem, w, h);
+    let mut canvas = screen.init_screen(&context.video_subsystem, game_state.scrn_w, game_state.scrn_h);
+    load_palette(&mut screen, &game_state);
+
+    let mut is_fullscreen = false;
+    let mut is_mouse_grabbed = false;
+    let mut frames: u64 = 0;
     while game_state.is_running {
         game_state::frame_start(&context.timer_subsystem, &mut game_state);
+        // Cambios de video hechos desde el menu de opciones
+        if game_state.is_fullscreen != is_fullscreen {
+            let mode = if game_state.is_fullscreen { FullscreenType::Desktop } else { FullscreenType::Off };
+            if let Err(e) = canvas.window_mut().set_fullscreen(mode) {
+                eprintln!("Error al cambiar a pantalla completa: {}", e);
+            }
+            is_fullscreen = game_state.is_fullscreen;
+        }
+        if !is_fullscreen && canvas.window().size() != (game_state.scrn_w, game_state.scrn_h)
+            && let Err(e) = canvas.window_mut().set_size(game_state.scrn_w, game_state.scrn_h) {
+            eprintln!("Error al cambiar la resolucion: {}", e);
+        }
+        // El mouse gira la camara solo mientras se juega
+        let grab_mouse = game_state.mode == menu::GameModeT::Playing && !game_state.console.is_open;
+        if grab_mouse != is_mouse_grabbed {
+            context.sdl_context.mouse().set_relative_mouse_mode(grab_mouse);
+            is_mouse_grabbed = grab_mouse;
+        }
+        let (rw, rh) = game_state::render_size(&game_state);
+        if screen.width != rw || screen.height != rh {
+            screen.resize(rw, rh);
+        }
+        let frame_timer = Instant::now();
         keyboard::handle_events(&mut context.event_pump, &mut keymap, &mut keystates, &mut game_state, &mut player);
-        screen.render(&mut canvas, w, h);
-        //renderer::render(&player, &game_state);
+
+        let sim_timer = Instant::now();
+        let tics = simulate(&mut game_state, &mut player);
+        let sim_time = sim_timer.elapsed().as_secs_f64();
+
+        let render_time = draw_frame(&mut screen, &mut game_state, &player, &keymap);
+        screen.render(&mut canvas, rw, rh);
+        if let Some(path) = game_state.screenshot_request.take() {
+            match screen.save_bmp(&path) {
+                Ok(()) => game_state.console.print(&format!("captura guardada: {}", path)),
+                Err(e) => game_state.console.print(&e),
+            }
+        }
+        frames += 1;
+        check_exit(&mut game_state, args, frames);
+
+        let frame_time = frame_timer.elapsed().as_secs_f64();
         game_state::frame_end(&context.timer_subsystem, &mut game_state);
+        debug::record_frame(&mut game_state.debug, game_state.delta_time, frame_time, sim_time, render_time, tics);
     }
+    finish(&mut screen, &game_state, args);
 }
+
+// Sin ventana ni SDL: frames de duracion fija (o los de la demo) lo mas rapido posible
+fn headless_loop(mut game_state: GameStateT, mut player: PlayerT, keymap: KeymapT, mut keystates: KeystatesT, args: &ArgsT) {
+    let mut screen: renderer::Screen = renderer::Screen::new();
+    load_palette(&mut screen, &game_state);
+
+    let run_timer = Instant::now();
+    let mut frames: u64 = 0;
+    while game_state.is_running {
+        game_state.delta_time = game_state.target_frame_time;
+        let (rw, rh) = game_state::render_size(&game_state);
+        if screen.width != rw || screen.height != rh {
+            screen.resize(rw, rh);
+        }
+        keyboard::update_player(&mut keystates, &mut game_state, &mut player);
+        simulate(&mut game_state, &mut player);
+        draw_frame(&mut screen, &mut game_state, &player, &keymap);
+        frames += 1;
+        check_exit(&mut game_state, args, frames);
+    }
+    let elapsed = run_timer.elapsed().as_secs_f64();
+    println!("{} frames, {} tics en {:.2}s ({:.1} FPS)", frames, game_state.tic, elapsed, frames as f64 / elapsed.max(1e-9));
+    finish(&mut screen, &game_state, args);
+}
+
+// Errores de arranque: mensaje y codigo de salida
+fn fail(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
+
 fn main() {
-    let width: u32 = 1024;
-    let height: u32 = 768;
-    let target_fps: f64 = 120.0;
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}\nUsar --help para ver las opciones", e);
+            std::process::exit(2);
+        }
+    };
+    if args.show_help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    if args.show_version {
+        println!("{}", cli::version());
+        return;
+    }
 
-    // Iniciar instancias de SDL (para usar la biblioteca)
-    let sdl_wrapper: SdlContextWrapper = SdlContextWrapper::init().unwrap();
-    let game_state: game_state::GameStateT = game_state::GameStateT::new(width, height, target_fps);
-    let player: PlayerT = PlayerT::new(40.0, 40.0, (height * 10) as f64, f64::consts::PI/2.0);
+    // Configuracion guardada por el menu de opciones (o la predeterminada), con lo que pise la linea de comandos
+    let settings = settings::load();
+    let mut game_state: game_state::GameStateT = game_state::GameStateT::new(&settings);
+    args.apply(&mut game_state);
+    let (width, height) = (game_state.scrn_w, game_state.scrn_h);
+
+    // WAD opcional con recursos (paleta, fuentes, sprites); si se pidio uno tiene que cargar
+    let wad_path = args.wad.as_deref().unwrap_or(wad::DEFAULT_WAD);
+    if args.wad.is_some() || std::path::Path::new(wad_path).exists() {
+        match wad::WadT::load(wad_path) {
+            Ok(wad) => {
+                println!("WAD cargado: {} ({}, {} lumps)", wad.path, if wad.is_iwad { "IWAD" } else { "PWAD" }, wad.lumps.len());
+                game_state.wad = Some(wad);
+            }
+            Err(e) if args.wad.is_some() => fail(&e),
+            Err(e) => eprintln!("Error al cargar el WAD: {}", e),
+        }
+    }
+    game_state.textures = textures::load_textures(game_state.wad.as_ref());
+    game_state.sprites = sprites::load_sprites(game_state.wad.as_ref(), &mut game_state.textures);
+    game_state.font = text::load_font(game_state.wad.as_ref());
+    println!("Fuente: {}", game_state.font.name);
+
+    // Orden de los mapas para pasar de nivel
+    match intermission::episode(args.episode.as_deref(), game_state.wad.as_ref()) {
+        Ok(episode) => game_state.episode = episode,
+        Err(e) if args.episode.is_some() => fail(&e),
+        Err(e) => eprintln!("Error al cargar el episodio: {}", e),
+    }
+
+    // Una demo trae su mapa y su inicio
+    let playback = args.play.as_deref().map(|path| demo::DemoT::load(path).unwrap_or_else(|e| fail(&e)));
+    let map_path = match (&playback, &args.map) {
+        (Some(demo), _) => demo.map_path.clone(),
+        (None, Some(name)) => cli::resolve_map(name, game_state.wad.as_ref()).unwrap_or_else(|e| fail(&e)),
+        (None, None) => intermission::first_map(&game_state),
+    };
+
+    // Sectores, objetos e inicio del jugador
+    let mut player: PlayerT = PlayerT::new(0.0, 0.0, 0.0, f64::consts::PI/2.0);
+    if let Err(e) = game_state::load_level(&mut game_state, &mut player, &map_path) {
+        fail(&format!("no se pudo cargar el mapa: {}", e));
+    }
+    let start = match (&playback, &args.start) {
+        (Some(demo), _) => Some((demo.start.clone(), demo.start_angle)),
+        (None, Some((position, angle))) => Some((position.clone(), angle.unwrap_or(player.dir_angle))),
+        (None, None) => None,
+    };
+    if let Some((position, angle)) = start {
+        if game_state.sector_at(position.x, position.y).is_none() {
+            fail(&format!("el inicio ({}, {}) esta fuera del mapa {}", position.x, position.y, map_path));
+        }
+        game_state::place_player(&game_state, &mut player, position, angle);
+    }
+    console::run_autoexec(&mut game_state, &mut player);
+
+    game_state.demo = playback;
+    if let Some(path) = &args.record {
+        game_state.demo = Some(demo::DemoT::record(path, &game_state.map_path, &player));
+    }
+    if args.skips_title() {
+        menu::set_mode(&mut game_state, menu::GameModeT::Playing);
+    }
 
     // Keyboard IO
-    let keymap: KeymapT = keyboard::KeymapT::new();
+    let keymap: KeymapT = settings.keymap.clone();
     let keystates: KeystatesT = keyboard::KeystatesT::new();
 
+    if args.headless {
+        headless_loop(game_state, player, keymap, keystates, &args);
+        return;
+    }
+
+    // Iniciar instancias de SDL (para usar la biblioteca)
+    let sdl_wrapper: SdlContextWrapper = SdlContextWrapper::init().unwrap_or_else(|e| fail(&format!("no se pudo iniciar SDL: {}", e)));
+
     // Window & Render init 
     window::init(&sdl_wrapper.video_subsystem, width, height);
     renderer::init(&sdl_wrapper.video_subsystem, &game_state);
 
-    // Sectores 
-    let mut s1: SectorT = SectorT::new(10, 5, 0xFF00FF, 0x0000FF, 0x00FF00);
-    let wall1 = WallT::new(0.0, 0.0, 10.0, 0.0, 5.0, 10.0, false);
-    let wall2 = WallT::new(10.0, 0.0, 10.0, 10.0, 5.0, 10.0, false);
-    
-    game_loop(sdl_wrapper, game_state, player, keymap, keystates, width, height);
-}
\ No newline at end of file
+    game_loop(sdl_wrapper, game_state, player, keymap, keystates, &args);
+}
@@ -0,0 +1,340 @@
+use std::fs;
+use crate::{game_state, lighting, movers, renderer::{SectorT, WallT}, specials::{self, SpecialT}, sprites::SpriteThingT, typedefs::Vec2T, utils::GameRngT};
+
+///////////////////////////////// FORMATO /////////////////////////////////
+// Archivo de texto, una entidad por linea ('#' para comentarios):
+//
+//   sector <id> <elevacion> <altura> <color> <color_piso> <color_techo> [opciones]
+//   wall <ax> <ay> <bx> <by> [opciones]
+//   thing <x> <y> <angulo> <sprite> [opciones]
+//   start <x> <y> <angulo>
+//
+// Las paredes pertenecen al ultimo sector declarado. Los ids de sector tienen
+// que ser consecutivos empezando en 0. Los colores aceptan 0xRRGGBB o decimal.
+// Sin 'start' el jugador aparece en el centro del sector 0 mirando hacia +y.
+//
+// Opciones de sector:
+//   light=<0..255>       nivel de luz base
+//   fx=<efecto>          flicker, fire, strobe_fast, strobe_slow,
+//                        strobe_fast_sync, strobe_slow_sync, glow
+//   light_min=<0..255>   nivel minimo del efecto (por defecto el vecino mas oscuro)
+//   light_from=<id>      copia la luz de otro sector
+//   sky=1                techo abierto (se dibuja el cielo)
+//   damage=<salud>       piso peligroso: lastima al jugador parado en el cada 32 tics
+//   mover=<tipo>         door (se abre al usarla, cerrada con altura 0), lift (baja al
+//                        usar su costado o al pisarlo) o crusher (arranca al entrar)
+//   speed=<unidades>     velocidad del sector movil por tic (por defecto la de Doom)
+//   wait=<tics>          espera abierta o abajo antes de volver
+//   tag=<n>              para que lo encuentren los specials de las paredes
+//   secret=1             sector secreto: suma a los secretos del nivel la primera vez que se entra
+//
+// Opciones de pared:
+//   portal=<id>          sector del otro lado
+//   mid=<textura>        textura enmascarada en el medio del portal (rejas, ventanas)
+//   alpha=<0..255>       opacidad de la textura del medio (en 8 bits todo < 255 es 50%)
+//   block_sound=1        el ruido pasa una pared asi pero no dos (vale para los dos lados del portal)
+//   special=<accion>     move (pone en marcha el sector movil), stop, light_on (luz del vecino
+//                        mas claro) o light_off (la del mas oscuro) sobre los sectores del tag,
+//                        o exit (termina el nivel)
+//   trigger=<como>       use (por defecto, con la tecla de usar), walk (al cruzarla) o shoot
+//   tag=<n>              sectores a los que apunta (sin tag: el del otro lado del portal)
+//   repeat=1             se puede disparar mas de una vez
+//
+// Opciones de thing (el angulo va en grados):
+//   frame=<letra>        cuadro del sprite (por defecto A)
+//   z=<altura>           altura de los pies (por defecto el piso del sector)
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+pub struct MapT {
+    pub sectors: Vec<SectorT>,
+    pub things: Vec<SpriteThingT>,
+    pub start: Vec2T,
+    pub start_angle: f64, // En radianes
+}
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+pub fn parse_color(token: &str) -> Result<u32, String> {
+    let parsed = match token.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => token.parse::<u32>(),
+    };
+    parsed.map_err(|_| format!("color invalido '{}'", token))
+}
+
+fn parse_num<T: std::str::FromStr>(token: &str, what: &str) -> Result<T, String> {
+    token.parse::<T>().map_err(|_| format!("{} invalido '{}'", what, token))
+}
+
+fn parse_flag(token: &str) -> Result<bool, String> {
+    match token {
+        "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" => Ok(false),
+        _ => Err(format!("valor booleano invalido '{}'", token)),
+    }
+}
+
+// Separa las opciones 'clave=valor' del resto de los argumentos
+fn split_options<'a>(tokens: &[&'a str]) -> (Vec<&'a str>, Vec<(&'a str, &'a str)>) {
+    let mut args = Vec::new();
+    let mut options = Vec::new();
+    for token in tokens {
+        match token.split_once('=') {
+            Some((key, value)) => options.push((key, value)),
+            None => args.push(*token),
+        }
+    }
+    (args, options)
+}
+
+fn parse_sector(args: &[&str], options: &[(&str, &str)], expected_id: usize) -> Result<SectorT, String> {
+    if args.len() != 6 {
+        return Err("se esperaba: sector <id> <elevacion> <altura> <color> <piso> <techo>".to_string());
+    }
+    let id: usize = parse_num(args[0], "id de sector")?;
+    if id != expected_id {
+        return Err(format!("id de sector {} fuera de orden (se esperaba {})", id, expected_id));
+    }
+
+    let elevation: i32 = parse_num(args[1], "elevacion")?;
+    let height: i32 = parse_num(args[2], "altura")?;
+    let mut sector = SectorT::new(height, elevation, parse_color(args[3])?, parse_color(args[5])?, parse_color(args[4])?);
+    sector.id = id as i32;
+
+    let (mut speed, mut wait) = (None, None);
+    for (key, value) in options {
+        match *key {
+            "light" => sector.light = parse_num::<i32>(value, "nivel de luz")?.clamp(0, 255),
+            "fx" => {
+                let (effect, in_sync) = lighting::parse_effect(value)?;
+                sector.light_fx.effect = effect;
+                sector.light_fx.in_sync = in_sync;
+            }
+            "light_min" => sector.light_fx.min_override = Some(parse_num::<i32>(value, "nivel de luz")?.clamp(0, 255)),
+            "light_from" => sector.light_fx.effect = lighting::LightEffectT::Transfer(parse_num(value, "id de sector")?),
+            "sky" => sector.is_sky = parse_flag(value)?,
+            "damage" => sector.damage = parse_num::<i32>(value, "daño")?.max(0),
+            "mover" => {
+                let (kind, default_speed, default_wait) = movers::parse_kind(value)?;
+                sector.mover.kind = kind;
+                sector.mover.speed = default_speed;
+                sector.mover.wait = default_wait;
+            }
+            "speed" => speed = Some(parse_num::<i32>(value, "velocidad")?.max(1)),
+            "wait" => wait = Some(parse_num::<i32>(value, "espera")?.max(0)),
+            "tag" => sector.tag = parse_num(value, "tag")?,
+            "secret" => sector.is_secret = parse_flag(value)?,
+            _ => return Err(format!("opcion de sector desconocida '{}'", key)),
+        }
+    }
+    if (speed.is_some() || wait.is_some()) && sector.mover.kind == movers::MoverKindT::None {
+        return Err("'speed' y 'wait' solo se pueden usar con 'mover'".to_string());
+    }
+    sector.mover.speed = speed.unwrap_or(sector.mover.speed);
+    sector.mover.wait = wait.unwrap_or(sector.mover.wait);
+    Ok(sector)
+}
+
+fn parse_wall(args: &[&str], options: &[(&str, &str)]) -> Result<WallT, String> {
+    if args.len() != 4 {
+        return Err("se esperaba: wall <ax> <ay> <bx> <by>".to_string());
+    }
+    let coords: Vec<f64> = args.iter().map(|t| parse_num(t, "coordenada")).collect::<Result<_, _>>()?;
+    let mut wall = WallT::new(coords[0], coords[1], coords[2], coords[3], 0.0, 0.0, false);
+    let mut special: Option<SpecialT> = None;
+    let (mut trigger, mut tag, mut is_repeatable) = (None, None, None);
+
+    for (key, value) in options {
+        match *key {
+            "portal" => {
+                wall.is_portal = true;
+                wall.neighbor = Some(parse_num(value, "id de sector")?);
+            }
+            "mid" => wall.mid_texture = Some(value.to_ascii_uppercase()),
+            "alpha" => wall.alpha = parse_num(value, "alpha")?,
+            "block_sound" => wall.blocks_sound = parse_flag(value)?,
+            "special" => special = Some(SpecialT {
+                action: specials::parse_action(value)?,
+                trigger: specials::LineTriggerT::Use,
+                tag: 0,
+                is_repeatable: false,
+                is_used: false,
+            }),
+            "trigger" => trigger = Some(specials::parse_trigger(value)?),
+            "tag" => tag = Some(parse_num(value, "tag")?),
+            "repeat" => is_repeatable = Some(parse_flag(value)?),
+            _ => return Err(format!("opcion de pared desconocida '{}'", key)),
+        }
+    }
+    match &mut special {
+        Some(special) => {
+            special.trigger = trigger.unwrap_or(special.trigger);
+            special.tag = tag.unwrap_or(special.tag);
+            special.is_repeatable = is_repeatable.unwrap_or(special.is_repeatable);
+        }
+        None if trigger.is_some() || tag.is_some() || is_repeatable.is_some() => {
+            return Err("'trigger', 'tag' y 'repeat' solo se pueden usar con 'special'".to_string());
+        }
+        None => {}
+    }
+    wall.special = special;
+    Ok(wall)
+}
+
+fn parse_start(args: &[&str]) -> Result<(Vec2T, f64), String> {
+    if args.len() != 3 {
+        return Err("se esperaba: start <x> <y> <angulo>".to_string());
+    }
+    let x: f64 = parse_num(args[0], "coordenada")?;
+    let y: f64 = parse_num(args[1], "coordenada")?;
+    let angle: f64 = parse_num(args[2], "angulo")?;
+    Ok((Vec2T::new(x, y), angle.to_radians()))
+}
+
+fn parse_thing(args: &[&str], options: &[(&str, &str)]) -> Result<(SpriteThingT, bool), String> {
+    if args.len() != 4 {
+        return Err("se esperaba: thing <x> <y> <angulo> <sprite>".to_string());
+    }
+    let x: f64 = parse_num(args[0], "coordenada")?;
+    let y: f64 = parse_num(args[1], "coordenada")?;
+    let angle: f64 = parse_num(args[2], "angulo")?;
+    if args[3].len() != 4 {
+        return Err(format!("nombre de sprite invalido '{}' (tiene que tener 4 letras)", args[3]));
+    }
+    let mut thing = SpriteThingT::new(x, y, 0.0, angle.to_radians(), args[3], 'A');
+    let mut has_z = false;
+
+    for (key, value) in options {
+        match *key {
+            "frame" => thing.frame = value.chars().next().ok_or("cuadro vacio".to_string())?.to_ascii_uppercase(),
+            "z" => {
+                thing.z = parse_num(value, "altura")?;
+                has_z = true;
+            }
+            _ => return Err(format!("opcion de thing desconocida '{}'", key)),
+        }
+    }
+    Ok((thing, has_z))
+}
+
+// Deja todos los sectores con el mismo sentido de giro (horario), asi el renderer
+// puede descartar las caras traseras comparando las x proyectadas
+fn normalize_winding(sector: &mut SectorT) {
+    let area: f64 = sector.walls.iter().map(|w| w.a.x * w.b.y - w.b.x * w.a.y).sum();
+    if area > 0.0 {
+        for wall in sector.walls.iter_mut() {
+            std::mem::swap(&mut wall.a, &mut wall.b);
+        }
+    }
+}
+
+// Verifica referencias entre sectores y completa los datos derivados
+fn link_sectors(sectors: &mut [SectorT]) -> Result<(), String> {
+    let count = sectors.len();
+    let heights: Vec<(f64, f64)> = sectors.iter().map(|s| (s.ceil_z(), s.floor_z())).collect();
+
+    for sector in sectors.iter_mut() {
+        if let lighting::LightEffectT::Transfer(src) = sector.light_fx.effect && src >= count {
+            return Err(format!("sector {}: light_from={} no existe", sector.id, src));
+        }
+        for wall in sector.walls.iter_mut() {
+            if wall.mid_texture.is_some() && wall.neighbor.is_none() {
+                return Err(format!("sector {}: 'mid' solo se puede usar en portales", sector.id));
+            }
+            if wall.blocks_sound && wall.neighbor.is_none() {
+                return Err(format!("sector {}: 'block_sound' solo se puede usar en portales", sector.id));
+            }
+            if let Some(n) = wall.neighbor {
+                if n >= count {
+                    return Err(format!("sector {}: portal a sector inexistente {}", sector.id, n));
+                }
+                wall.portal_top_height = heights[n].0;
+                wall.portal_bot_height = heights[n].1;
+            }
+        }
+        normalize_winding(sector);
+    }
+    mirror_sound_blocks(sectors);
+    Ok(())
+}
+
+// Alcanza con marcar un lado del portal: el del otro sector se marca igual
+fn mirror_sound_blocks(sectors: &mut [SectorT]) {
+    let mut blocked = Vec::new();
+    for (index, sector) in sectors.iter().enumerate() {
+        for wall in sector.walls.iter().filter(|w| w.blocks_sound) {
+            blocked.extend(wall.neighbor.map(|n| (n, index, wall.a.clone(), wall.b.clone())));
+        }
+    }
+    for (sector, neighbor, a, b) in blocked {
+        let same_edge = |w: &WallT| (w.a == a && w.b == b) || (w.a == b && w.b == a);
+        for wall in sectors[sector].walls.iter_mut().filter(|w| w.neighbor == Some(neighbor) && same_edge(w)) {
+            wall.blocks_sound = true;
+        }
+    }
+}
+
+pub fn parse_map(source: &str) -> Result<MapT, String> {
+    let mut sectors: Vec<SectorT> = Vec::new();
+    let mut things: Vec<(SpriteThingT, bool, usize)> = Vec::new();
+    let mut start: Option<(Vec2T, f64, usize)> = None;
+
+    for (line_num, raw_line) in source.lines().enumerate() {
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (args, options) = split_options(&tokens[1..]);
+        let result = match tokens[0] {
+            "sector" => parse_sector(&args, &options, sectors.len()).map(|s| sectors.push(s)),
+            "wall" => match sectors.last_mut() {
+                Some(sector) => parse_wall(&args, &options).map(|w| sector.add_wall(w)),
+                None => Err("pared declarada antes de cualquier sector".to_string()),
+            },
+            "thing" => parse_thing(&args, &options).map(|(t, has_z)| things.push((t, has_z, line_num + 1))),
+            "start" => parse_start(&args).map(|(pos, angle)| start = Some((pos, angle, line_num + 1))),
+            other => Err(format!("entrada desconocida '{}'", other)),
+        };
+        result.map_err(|e| format!("linea {}: {}", line_num + 1, e))?;
+    }
+
+    if sectors.is_empty() {
+        return Err("el mapa no tiene sectores".to_string());
+    }
+    link_sectors(&mut sectors)?;
+    // Misma semilla que la simulacion: el mapa arranca siempre con las mismas luces
+    lighting::init_sector_lights(&mut sectors, &mut GameRngT::new(game_state::RNG_SEED));
+
+    // Cada thing tiene que estar dentro de un sector; sin z se apoya en el piso
+    let mut placed = Vec::with_capacity(things.len());
+    for (mut thing, has_z, line) in things {
+        let Some(sector) = sectors.iter().find(|s| s.contains(thing.position.x, thing.position.y)) else {
+            return Err(format!("linea {}: thing fuera de todos los sectores", line));
+        };
+        if !has_z {
+            thing.z = sector.floor_z();
+        }
+        placed.push(thing);
+    }
+
+    let (start, start_angle) = match start {
+        Some((pos, angle, line)) => {
+            if !sectors.iter().any(|s| s.contains(pos.x, pos.y)) {
+                return Err(format!("linea {}: inicio fuera de todos los sectores", line));
+            }
+            (pos, angle)
+        }
+        None => {
+            let walls = &sectors[0].walls;
+            let n = walls.len().max(1) as f64;
+            let center = Vec2T::new(walls.iter().map(|w| w.a.x).sum::<f64>() / n, walls.iter().map(|w| w.a.y).sum::<f64>() / n);
+            (center, std::f64::consts::FRAC_PI_2)
+        }
+    };
+    Ok(MapT { sectors, things: placed, start, start_angle })
+}
+
+pub fn load_map(path: &str) -> Result<MapT, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("no se pudo leer '{}': {}", path, e))?;
+    parse_map(&source).map_err(|e| format!("{}: {}", path, e))
+}
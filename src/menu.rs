@@ -0,0 +1,375 @@
+use sdl2::keyboard::Scancode;
+use crate::{game_state::{self, GameStateT}, intermission, keyboard::{self, KeymapT}, player::PlayerT, renderer::Screen, settings, text::{self, AlignT, TextStyleT}};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const TITLE: &str = "DOOM ENGINE";
+pub const TITLE_CLR: u32 = 0xC02020;
+pub const ITEM_CLR: u32 = 0xA0A0A0;
+pub const SELECTED_CLR: u32 = 0xFFD040;
+pub const HINT_CLR: u32 = 0x808080;
+pub const DIM_CLR: u32 = 0x000000;
+pub const DIM_ALPHA: u8 = 160;
+pub const TITLE_SCALE: i32 = 8;
+pub const ITEM_SCALE: i32 = 4;
+pub const OPTION_SCALE: i32 = 3;  // Las listas de opciones son largas
+
+// Limites y pasos de los valores ajustables
+pub const FOV_MIN: f64 = 60.0;
+pub const FOV_MAX: f64 = 120.0;
+pub const FOV_STEP: f64 = 5.0;
+pub const MOUSE_MAX: f64 = 20.0;
+pub const VOLUME_STEP: u32 = 10;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Estados del juego: que se dibuja y a donde van las teclas
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameModeT {
+    Title,
+    MainMenu,
+    Playing,
+    Paused,
+    Options,
+    Controls,
+    QuitConfirm,
+    Intermission, // Resumen al terminar un nivel
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuActionT {
+    NewGame,
+    Resume,
+    Options,
+    MainMenu,
+    Quit,
+    Back,
+    Resolution,
+    ToggleFullscreen,
+    ToggleFpsCap,
+    FpsLimit,
+    Fov,
+    MouseSensitivity,
+    Volume,
+    TogglePalette,
+    Controls,
+    Rebind(&'static str), // Accion del KeymapT
+}
+
+pub struct MenuItemT {
+    pub label: &'static str,
+    pub action: MenuActionT,
+}
+
+pub struct MenuT {
+    pub selected: usize,
+    pub return_mode: GameModeT, // A donde vuelven las opciones y la confirmacion de salida
+    pub rebinding: Option<&'static str>, // Accion que espera su tecla nueva
+}
+    impl MenuT {
+        pub fn new() -> Self {
+            MenuT { selected: 0, return_mode: GameModeT::MainMenu, rebinding: None }
+        }
+    }
+
+const MAIN_ITEMS: [MenuItemT; 3] = [
+    MenuItemT { label: "NEW GAME", action: MenuActionT::NewGame },
+    MenuItemT { label: "OPTIONS", action: MenuActionT::Options },
+    MenuItemT { label: "QUIT", action: MenuActionT::Quit },
+];
+
+const PAUSE_ITEMS: [MenuItemT; 4] = [
+    MenuItemT { label: "RESUME", action: MenuActionT::Resume },
+    MenuItemT { label: "OPTIONS", action: MenuActionT::Options },
+    MenuItemT { label: "MAIN MENU", action: MenuActionT::MainMenu },
+    MenuItemT { label: "QUIT", action: MenuActionT::Quit },
+];
+
+const OPTION_ITEMS: [MenuItemT; 10] = [
+    MenuItemT { label: "RESOLUTION", action: MenuActionT::Resolution },
+    MenuItemT { label: "FULLSCREEN", action: MenuActionT::ToggleFullscreen },
+    MenuItemT { label: "FPS CAP", action: MenuActionT::ToggleFpsCap },
+    MenuItemT { label: "FPS LIMIT", action: MenuActionT::FpsLimit },
+    MenuItemT { label: "FIELD OF VIEW", action: MenuActionT::Fov },
+    MenuItemT { label: "MOUSE SENSITIVITY", action: MenuActionT::MouseSensitivity },
+    MenuItemT { label: "VOLUME", action: MenuActionT::Volume },
+    MenuItemT { label: "8-BIT PALETTE", action: MenuActionT::TogglePalette },
+    MenuItemT { label: "CONTROLS", action: MenuActionT::Controls },
+    MenuItemT { label: "BACK", action: MenuActionT::Back },
+];
+
+const CONTROL_ITEMS: [MenuItemT; 19] = [
+    MenuItemT { label: "MOVE FORWARD", action: MenuActionT::Rebind("forward") },
+    MenuItemT { label: "MOVE BACKWARD", action: MenuActionT::Rebind("backward") },
+    MenuItemT { label: "TURN LEFT", action: MenuActionT::Rebind("left") },
+    MenuItemT { label: "TURN RIGHT", action: MenuActionT::Rebind("right") },
+    MenuItemT { label: "STRAFE LEFT", action: MenuActionT::Rebind("strafe_left") },
+    MenuItemT { label: "STRAFE RIGHT", action: MenuActionT::Rebind("strafe_right") },
+    MenuItemT { label: "FLY UP", action: MenuActionT::Rebind("up") },
+    MenuItemT { label: "FLY DOWN", action: MenuActionT::Rebind("down") },
+    MenuItemT { label: "FIRE", action: MenuActionT::Rebind("fire") },
+    MenuItemT { label: "USE", action: MenuActionT::Rebind("activate") },
+    MenuItemT { label: "NEXT WEAPON", action: MenuActionT::Rebind("next_weapon") },
+    MenuItemT { label: "PREV WEAPON", action: MenuActionT::Rebind("prev_weapon") },
+    MenuItemT { label: "AUTOMAP", action: MenuActionT::Rebind("toggle_map") },
+    MenuItemT { label: "CONSOLE", action: MenuActionT::Rebind("console") },
+    MenuItemT { label: "DEBUG", action: MenuActionT::Rebind("debug_mode") },
+    MenuItemT { label: "PALETTE", action: MenuActionT::Rebind("toggle_palette") },
+    MenuItemT { label: "QUICKSAVE", action: MenuActionT::Rebind("quicksave") },
+    MenuItemT { label: "QUICKLOAD", action: MenuActionT::Rebind("quickload") },
+    MenuItemT { label: "BACK", action: MenuActionT::Back },
+];
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+fn items(mode: GameModeT) -> &'static [MenuItemT] {
+    match mode {
+        GameModeT::MainMenu => &MAIN_ITEMS,
+        GameModeT::Paused => &PAUSE_ITEMS,
+        GameModeT::Options => &OPTION_ITEMS,
+        GameModeT::Controls => &CONTROL_ITEMS,
+        _ => &[],
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "ON" } else { "OFF" }
+}
+
+// Texto de una opcion, con su valor actual si tiene
+fn item_label(item: &MenuItemT, state: &GameStateT, keymap: &KeymapT) -> String {
+    match item.action {
+        MenuActionT::Resolution => format!("{}: {}X{}", item.label, state.scrn_w, state.scrn_h),
+        MenuActionT::ToggleFullscreen => format!("{}: {}", item.label, on_off(state.is_fullscreen)),
+        MenuActionT::ToggleFpsCap => format!("{}: {}", item.label, on_off(state.is_fps_capped)),
+        MenuActionT::FpsLimit => format!("{}: {:.0}", item.label, state.target_fps),
+        MenuActionT::Fov => format!("{}: {:.0}", item.label, state.fov.to_degrees()),
+        MenuActionT::MouseSensitivity => format!("{}: {:.0}", item.label, state.mouse_sensitivity),
+        MenuActionT::Volume => format!("{}: {}%", item.label, state.volume),
+        MenuActionT::TogglePalette => format!("{}: {}", item.label, on_off(state.is_palette_mode)),
+        MenuActionT::Rebind(action) if state.menu.rebinding == Some(action) => format!("{}: ...", item.label),
+        MenuActionT::Rebind(action) => match keymap.binding(action) {
+            Some(scancode) => format!("{}: {}", item.label, scancode.name()),
+            None => item.label.to_string(),
+        },
+        _ => item.label.to_string(),
+    }
+}
+
+// Siguiente (o anterior) valor de una lista, partiendo del mas cercano al actual
+fn cycle<T: Copy>(values: &[T], current: usize, step: i32) -> T {
+    let len = values.len() as i32;
+    values[(current as i32 + step).rem_euclid(len) as usize]
+}
+
+fn closest(values: &[f64], current: f64) -> usize {
+    (0..values.len()).min_by(|&a, &b| (values[a] - current).abs().total_cmp(&(values[b] - current).abs())).unwrap_or(0)
+}
+
+// Flechas izquierda/derecha (step -1/+1) en las opciones con valor
+fn adjust(action: MenuActionT, step: i32, state: &mut GameStateT) {
+    match action {
+        MenuActionT::Resolution => {
+            let current = settings::RESOLUTIONS.iter().position(|&r| r == (state.scrn_w, state.scrn_h));
+            let (w, h) = match current {
+                Some(index) => cycle(&settings::RESOLUTIONS, index, step),
+                None => settings::RESOLUTIONS[0],
+            };
+            state.scrn_w = w;
+            state.scrn_h = h;
+            state.settings.width = w;
+            state.settings.height = h;
+        }
+        MenuActionT::FpsLimit => {
+            let fps = cycle(&settings::FPS_LIMITS, closest(&settings::FPS_LIMITS, state.target_fps), step);
+            state.target_fps = fps;
+            state.target_frame_time = 1.0 / fps;
+            state.settings.target_fps = fps;
+        }
+        MenuActionT::Fov => {
+            let fov = (state.fov.to_degrees().round() + FOV_STEP * step as f64).clamp(FOV_MIN, FOV_MAX);
+            state.fov = fov.to_radians();
+            state.settings.fov = fov;
+        }
+        MenuActionT::MouseSensitivity => {
+            state.mouse_sensitivity = (state.mouse_sensitivity.round() + step as f64).clamp(0.0, MOUSE_MAX);
+            state.settings.mouse_sensitivity = state.mouse_sensitivity;
+        }
+        MenuActionT::Volume => {
+            state.volume = (state.volume as i32 + VOLUME_STEP as i32 * step).clamp(0, 100) as u32;
+            state.settings.volume = state.volume;
+        }
+        MenuActionT::ToggleFullscreen => {
+            state.is_fullscreen = !state.is_fullscreen;
+            state.settings.fullscreen = state.is_fullscreen;
+        }
+        MenuActionT::ToggleFpsCap => {
+            state.is_fps_capped = !state.is_fps_capped;
+            state.settings.fps_capped = state.is_fps_capped;
+        }
+        MenuActionT::TogglePalette => state.is_palette_mode = !state.is_palette_mode,
+        _ => {}
+    }
+}
+
+// Escribe la configuracion al salir de las opciones: lo guardado con lo que se cambio en el menu
+fn save_settings(state: &mut GameStateT, keymap: &KeymapT) {
+    state.settings.keymap = keymap.clone();
+    if let Err(e) = settings::save(&state.settings) {
+        state.console.print(&format!("no se pudo guardar la configuracion: {}", e));
+    }
+}
+
+// Cambia de estado; fuera de Playing la simulacion queda congelada
+pub fn set_mode(state: &mut GameStateT, mode: GameModeT) {
+    // Controles vuelve a las opciones, que ya recuerdan de donde se vino
+    if matches!(mode, GameModeT::Options | GameModeT::QuitConfirm) && state.mode != GameModeT::Controls {
+        state.menu.return_mode = state.mode;
+    }
+    state.mode = mode;
+    state.is_paused = mode != GameModeT::Playing;
+    state.menu.selected = 0;
+    state.menu.rebinding = None;
+}
+
+fn run_action(action: MenuActionT, keymap: &KeymapT, state: &mut GameStateT, player: &mut PlayerT) {
+    match action {
+        MenuActionT::NewGame => {
+            // Partida nueva: primer mapa del episodio con el jugador recien creado
+            let path = intermission::first_map(state);
+            *player = PlayerT::new(0.0, 0.0, 0.0, 0.0);
+            match game_state::load_level(state, player, &path) {
+                Ok(()) => set_mode(state, GameModeT::Playing),
+                Err(e) => state.console.print(&e),
+            }
+        }
+        MenuActionT::Resume => set_mode(state, GameModeT::Playing),
+        MenuActionT::Options => set_mode(state, GameModeT::Options),
+        MenuActionT::MainMenu => set_mode(state, GameModeT::MainMenu),
+        MenuActionT::Quit => set_mode(state, GameModeT::QuitConfirm),
+        MenuActionT::Back => back(keymap, state),
+        MenuActionT::Controls => set_mode(state, GameModeT::Controls),
+        MenuActionT::Rebind(name) => state.menu.rebinding = Some(name),
+        _ => adjust(action, 1, state),
+    }
+}
+
+// Escape vuelve un nivel atras
+fn back(keymap: &KeymapT, state: &mut GameStateT) {
+    match state.mode {
+        GameModeT::Paused => set_mode(state, GameModeT::Playing),
+        GameModeT::Options => {
+            save_settings(state, keymap);
+            let previous = state.menu.return_mode;
+            set_mode(state, previous);
+        }
+        GameModeT::Controls => {
+            set_mode(state, GameModeT::Options);
+            state.menu.selected = OPTION_ITEMS.iter().position(|item| item.action == MenuActionT::Controls).unwrap_or(0);
+        }
+        GameModeT::QuitConfirm => {
+            let previous = state.menu.return_mode;
+            set_mode(state, previous);
+        }
+        GameModeT::MainMenu => set_mode(state, GameModeT::Title),
+        _ => {}
+    }
+}
+
+// Teclas fuera del juego
+pub fn handle_key(scancode: Scancode, keymap: &mut KeymapT, state: &mut GameStateT, player: &mut PlayerT) {
+    // Escape cancela; cualquier otra tecla queda asignada a la accion
+    if let Some(action) = state.menu.rebinding.take() {
+        if scancode != Scancode::Escape && let Some(previous) = keymap.binding(action) {
+            // Si otra accion usaba esa tecla se queda con la anterior (intercambio)
+            let taken = keyboard::ACTIONS.iter().find(|&&other| other != action && keymap.binding(other) == Some(scancode));
+            if let Some(&other) = taken && let Some(slot) = keymap.binding_mut(other) {
+                *slot = previous;
+            }
+            if let Some(slot) = keymap.binding_mut(action) {
+                *slot = scancode;
+            }
+        }
+        return;
+    }
+    match state.mode {
+        GameModeT::Title => set_mode(state, GameModeT::MainMenu),
+        GameModeT::Intermission => intermission::advance(state, player),
+        GameModeT::QuitConfirm => match scancode {
+            Scancode::Y | Scancode::Return | Scancode::KpEnter => state.is_running = false,
+            Scancode::N | Scancode::Escape => back(keymap, state),
+            _ => {}
+        },
+        GameModeT::Playing => {}
+        mode => {
+            let count = items(mode).len();
+            match scancode {
+                Scancode::Up | Scancode::W => state.menu.selected = (state.menu.selected + count - 1) % count,
+                Scancode::Down | Scancode::S => state.menu.selected = (state.menu.selected + 1) % count,
+                Scancode::Return | Scancode::KpEnter | Scancode::Space => {
+                    let action = items(mode)[state.menu.selected].action;
+                    run_action(action, keymap, state, player);
+                }
+                Scancode::Left | Scancode::A => adjust(items(mode)[state.menu.selected].action, -1, state),
+                Scancode::Right | Scancode::D => adjust(items(mode)[state.menu.selected].action, 1, state),
+                Scancode::Escape => back(keymap, state),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw_centered(screen: &mut Screen, state: &GameStateT, y: i32, line: &str, color: u32, scale: i32) {
+    let style = TextStyleT::new(color, scale).aligned(AlignT::Center);
+    text::draw_text(screen, &state.font, screen.width as i32 / 2, y, line, &style);
+}
+
+// Dibuja el menu del estado actual encima de la vista oscurecida
+pub fn draw(screen: &mut Screen, state: &GameStateT, keymap: &KeymapT) {
+    if state.mode == GameModeT::Playing {
+        return;
+    }
+    let (w, h) = (screen.width as i32, screen.height as i32);
+    screen.blend_rect(0, 0, w, h, DIM_CLR, DIM_ALPHA);
+    // Escala segun la altura para que el menu ocupe lo mismo a cualquier resolucion
+    let unit = (h / 240).max(1);
+    let title_y = h / 5;
+    let item_h = state.font.line_height(ITEM_SCALE * unit / 2);
+
+    match state.mode {
+        GameModeT::Intermission => intermission::draw(screen, state),
+        GameModeT::Title => {
+            draw_centered(screen, state, title_y, TITLE, TITLE_CLR, TITLE_SCALE * unit / 2);
+            draw_centered(screen, state, h * 2 / 3, "PRESS ANY KEY", HINT_CLR, ITEM_SCALE * unit / 2);
+        }
+        GameModeT::QuitConfirm => {
+            draw_centered(screen, state, h / 2 - item_h, "QUIT GAME?", SELECTED_CLR, ITEM_SCALE * unit / 2);
+            draw_centered(screen, state, h / 2 + item_h, "(Y/N)", HINT_CLR, ITEM_SCALE * unit / 2);
+        }
+        mode => {
+            let header = match mode {
+                GameModeT::Paused => "PAUSED",
+                GameModeT::Options => "OPTIONS",
+                GameModeT::Controls => "CONTROLS",
+                _ => TITLE,
+            };
+            draw_centered(screen, state, title_y, header, TITLE_CLR, TITLE_SCALE * unit / 2);
+            let is_long = matches!(mode, GameModeT::Options | GameModeT::Controls);
+            let scale = if is_long { OPTION_SCALE } else { ITEM_SCALE } * unit / 2;
+            let line_h = state.font.line_height(scale);
+            let top = if is_long { title_y + state.font.line_height(TITLE_SCALE * unit / 2) } else { h / 2 - item_h };
+            for (i, item) in items(mode).iter().enumerate() {
+                let selected = i == state.menu.selected;
+                let label = item_label(item, state, keymap);
+                let line = if selected { format!("> {} <", label) } else { label };
+                let color = if selected { SELECTED_CLR } else { ITEM_CLR };
+                draw_centered(screen, state, top + i as i32 * line_h, &line, color, scale);
+            }
+            if state.menu.rebinding.is_some() {
+                draw_centered(screen, state, h - line_h * 2, "PRESS A KEY (ESC TO CANCEL)", HINT_CLR, scale);
+            } else if mode == GameModeT::Controls {
+                draw_centered(screen, state, h - line_h * 2, "ENTER TO CHANGE A KEY", HINT_CLR, scale);
+            } else if mode == GameModeT::Options {
+                draw_centered(screen, state, h - line_h * 2, "LEFT/RIGHT TO CHANGE", HINT_CLR, scale);
+            }
+        }
+    }
+}
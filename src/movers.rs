@@ -0,0 +1,260 @@
+use crate::{ai, collision, entities::{self, EntityIdT}, game_state::GameStateT, player::{self, PlayerT}, renderer::SectorT, typedefs::Vec2T};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+// Velocidades en unidades por tic y esperas en tics (las de Doom)
+pub const DOOR_SPEED: i32 = 2;
+pub const DOOR_WAIT: i32 = 150;
+pub const DOOR_LIP: i32 = 4;       // La puerta abierta queda un poco bajo el techo vecino mas bajo
+pub const LIFT_SPEED: i32 = 4;
+pub const LIFT_WAIT: i32 = 105;
+pub const CRUSHER_SPEED: i32 = 1;
+pub const CRUSHER_GAP: i32 = 8;    // Hasta donde baja la trituradora sobre el piso
+pub const CRUSH_DAMAGE: i32 = 10;  // Daño cada CRUSH_TICS a lo que no entra
+pub const CRUSH_TICS: u64 = 4;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MoverKindT {
+    None,
+    Door,    // Sube el techo, espera y lo vuelve a bajar hasta el piso
+    Lift,    // Baja el piso al vecino mas bajo, espera y vuelve a subir
+    Crusher, // Baja y sube el techo sin parar lastimando lo que queda abajo
+}
+
+// Como se activa un sector que se mueve
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TriggerT {
+    Use,     // El jugador usa su portal (puertas y costados de ascensores)
+    Walk,    // El jugador entra al sector
+    Monster, // Un monstruo choca contra el portal (solo abre puertas)
+}
+
+#[derive(Clone)]
+pub struct MoverT {
+    pub kind: MoverKindT,
+    pub speed: i32,
+    pub wait: i32,
+    pub direction: i32, // 1 sube, -1 baja, 0 quieto (o esperando si count > 0)
+    pub count: i32,     // Tics de espera que quedan antes de volver
+    pub low: i32,       // Limites del plano que se mueve (se calculan al activarlo)
+    pub high: i32,
+}
+    impl MoverT {
+        pub fn new() -> Self {
+            MoverT {
+                kind: MoverKindT::None,
+                speed: 0,
+                wait: 0,
+                direction: 0,
+                count: 0,
+                low: 0,
+                high: 0,
+            }
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+
+// Traduce el nombre usado en el archivo de mapa a un tipo (con su velocidad y espera por defecto)
+pub fn parse_kind(name: &str) -> Result<(MoverKindT, i32, i32), String> {
+    match name {
+        "door" => Ok((MoverKindT::Door, DOOR_SPEED, DOOR_WAIT)),
+        "lift" => Ok((MoverKindT::Lift, LIFT_SPEED, LIFT_WAIT)),
+        "crusher" => Ok((MoverKindT::Crusher, CRUSHER_SPEED, 0)),
+        _ => Err(format!("tipo de sector movil desconocido '{}'", name)),
+    }
+}
+
+pub fn is_active(mover: &MoverT) -> bool {
+    mover.direction != 0 || mover.count > 0
+}
+
+fn neighbors(sectors: &[SectorT], index: usize) -> impl Iterator<Item = &SectorT> {
+    sectors[index].walls.iter().filter_map(|w| w.neighbor).map(|n| &sectors[n])
+}
+
+// Activa el sector si responde a ese disparador; devuelve si hizo algo
+pub fn activate(sectors: &mut [SectorT], index: usize, trigger: TriggerT) -> bool {
+    let responds = match sectors[index].mover.kind {
+        MoverKindT::Door => true,
+        MoverKindT::Lift => trigger != TriggerT::Monster,
+        MoverKindT::Crusher => trigger == TriggerT::Walk,
+        MoverKindT::None => false,
+    };
+    responds && start(sectors, index)
+}
+
+// Pone en marcha el sector (tambien lo usan los specials con tag); devuelve si hizo algo
+pub fn start(sectors: &mut [SectorT], index: usize) -> bool {
+    let lowest_ceil = neighbors(sectors, index).map(|s| s.ceil_z() as i32).min();
+    let lowest_floor = neighbors(sectors, index).map(|s| s.elevation).min();
+    let sector = &mut sectors[index];
+    let (floor, ceil) = (sector.elevation, sector.elevation + sector.height);
+    let mover = &mut sector.mover;
+    match mover.kind {
+        MoverKindT::Door => {
+            // Abierta o abriendose no se hace nada; cerrandose vuelve a abrir
+            if mover.direction == 1 || mover.count > 0 {
+                return false;
+            }
+            mover.low = floor;
+            mover.high = lowest_ceil.map_or(ceil, |c| c - DOOR_LIP).max(floor);
+            mover.direction = 1;
+        }
+        MoverKindT::Lift => {
+            if is_active(mover) {
+                return false;
+            }
+            mover.low = lowest_floor.map_or(floor, |f| f.min(floor));
+            mover.high = floor;
+            mover.direction = -1;
+        }
+        MoverKindT::Crusher => {
+            if is_active(mover) {
+                return false;
+            }
+            // Si se la habia frenado sigue desde donde quedo
+            if mover.high <= mover.low {
+                mover.high = ceil;
+            }
+            mover.low = floor + CRUSHER_GAP;
+            mover.direction = -1;
+        }
+        MoverKindT::None => return false,
+    }
+    true
+}
+
+// Frena el sector donde esta (las trituradoras quedan asi hasta que se las vuelva a activar)
+pub fn stop(sectors: &mut [SectorT], index: usize) -> bool {
+    let mover = &mut sectors[index].mover;
+    let was_active = is_active(mover);
+    mover.direction = 0;
+    mover.count = 0;
+    was_active
+}
+
+// Sectores del otro lado de los portales contra los que empuja un cuerpo que va de from a to
+pub fn pushed_portals(sectors: &[SectorT], from: &Vec2T, to: &Vec2T, radius: f64) -> Vec<usize> {
+    let Some(current) = entities::find_sector(sectors, from.x, from.y) else { return Vec::new(); };
+    let mut pushed: Vec<usize> = sectors[current].walls.iter()
+        .filter(|w| {
+            let dist = collision::distance_to_wall(to, w);
+            dist < radius && dist < collision::distance_to_wall(from, w)
+        })
+        .filter_map(|w| w.neighbor)
+        .collect();
+    pushed.dedup();
+    pushed
+}
+
+// Lo que ocupa el sector (o lo toca desde un vecino): el jugador y los things solidos
+fn occupants(state: &GameStateT, player: &PlayerT, index: usize) -> (bool, Vec<EntityIdT>) {
+    let sectors = &state.sectors;
+    let has_player = collision::touches_sector(sectors, index, &player.position, collision::PLAYER_RADIUS);
+    let things = collision::nearby_sectors(sectors, index).into_iter().flat_map(|s| state.entities.in_sector(s))
+        .filter(|e| e.is_solid && e.projectile.is_none())
+        .filter(|e| collision::touches_sector(sectors, index, &e.thing.position, e.radius))
+        .map(|e| e.id)
+        .collect();
+    (has_player, things)
+}
+
+// Lo que no entra entre floor y ceil (lo que esta en el piso sube con el): si el jugador y que things
+fn stuck(state: &GameStateT, player: &PlayerT, index: usize, floor: f64, ceil: f64) -> (bool, Vec<EntityIdT>) {
+    let (has_player, things) = occupants(state, player, index);
+    let old_floor = state.sectors[index].floor_z();
+    let feet = |z: f64| if z <= old_floor { floor } else { z.max(floor) };
+    let player_stuck = has_player && !player::is_dead(player) && !player.is_noclip
+        && ceil - feet(player.z - player::EYE_HEIGHT) < collision::PLAYER_HEIGHT;
+    let things = things.into_iter().filter(|&id| {
+        state.entities.get(id).is_some_and(|e| ceil - feet(e.thing.z) < e.height)
+    }).collect();
+    (player_stuck, things)
+}
+
+// Lleva a los things apoyados en el piso del sector hasta la nueva altura
+fn carry_things(state: &mut GameStateT, index: usize, old_floor: f64) {
+    let new_floor = state.sectors[index].floor_z();
+    let riding: Vec<EntityIdT> = state.entities.in_sector(index)
+        .filter(|e| e.projectile.is_none() && (e.thing.z - old_floor).abs() < 1e-6)
+        .map(|e| e.id).collect();
+    for id in riding {
+        if let Some(entity) = state.entities.get_mut(id) {
+            entity.thing.z = new_floor;
+        }
+    }
+}
+
+// Avanza un tic el sector index; el plano va de a speed unidades hacia su limite
+fn move_sector(state: &mut GameStateT, player: &mut PlayerT, index: usize) {
+    let sector = &state.sectors[index];
+    let mover = sector.mover.clone();
+    if mover.direction == 0 {
+        if mover.count > 0 {
+            let mover = &mut state.sectors[index].mover;
+            mover.count -= 1;
+            if mover.count == 0 {
+                mover.direction = if mover.kind == MoverKindT::Lift { 1 } else { -1 };
+            }
+        }
+        return;
+    }
+
+    let moves_floor = mover.kind == MoverKindT::Lift;
+    let current = if moves_floor { sector.elevation } else { sector.elevation + sector.height };
+    let target = if mover.direction > 0 { mover.high } else { mover.low };
+    let next = if mover.direction > 0 { (current + mover.speed).min(target) } else { (current - mover.speed).max(target) };
+    let (floor, ceil) = if moves_floor { (next, sector.elevation + sector.height) } else { (sector.elevation, next) };
+    let closing = if moves_floor { mover.direction > 0 } else { mover.direction < 0 };
+
+    if closing {
+        let (player_stuck, crushed) = stuck(state, player, index, floor as f64, ceil as f64);
+        if player_stuck || !crushed.is_empty() {
+            if mover.kind != MoverKindT::Crusher {
+                // Como en Doom: la puerta vuelve a abrir y el ascensor vuelve a bajar
+                state.sectors[index].mover.direction = -mover.direction;
+                return;
+            }
+            if state.tic.is_multiple_of(CRUSH_TICS) {
+                if player_stuck {
+                    player::damage(player, CRUSH_DAMAGE);
+                }
+                for id in crushed {
+                    ai::damage_monster(state, player, id, CRUSH_DAMAGE);
+                }
+            }
+        }
+    }
+
+    let old_floor = state.sectors[index].floor_z();
+    let sector = &mut state.sectors[index];
+    sector.height = ceil - floor;
+    sector.elevation = floor;
+    if moves_floor {
+        carry_things(state, index, old_floor);
+    }
+
+    if next == target {
+        let mover = &mut state.sectors[index].mover;
+        match mover.kind {
+            MoverKindT::Crusher => mover.direction = -mover.direction,
+            // Abierta o abajo: espera; cerrada o arriba: termina
+            _ if mover.direction == if moves_floor { -1 } else { 1 } => {
+                mover.direction = 0;
+                mover.count = mover.wait.max(1);
+            }
+            _ => mover.direction = 0,
+        }
+    }
+}
+
+// Avanza un tic todos los sectores en movimiento
+pub fn tick(state: &mut GameStateT, player: &mut PlayerT) {
+    for index in 0..state.sectors.len() {
+        if is_active(&state.sectors[index].mover) {
+            move_sector(state, player, index);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::wad::WadT;
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const NUM_PALETTES: usize = 14;
+pub const NUM_LIGHT_MAPS: usize = 32;
+pub const NUM_COLORMAPS: usize = 34; // 32 niveles de luz + invulnerabilidad + negro
+
+// Paletas especiales de PLAYPAL (igual que en Doom)
+pub const DAMAGE_PALETTE_START: usize = 1;
+pub const NUM_DAMAGE_PALETTES: usize = 8;
+pub const BONUS_PALETTE_START: usize = 9;
+pub const NUM_BONUS_PALETTES: usize = 4;
+pub const RADIATION_PALETTE: usize = 13;
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+pub struct PaletteT {
+    pub palettes: Vec<[u32; 256]>, // 0x00RRGGBB por indice
+    pub colormaps: Vec<[u8; 256]>,
+    pub tranmap: Vec<u8>, // [frente * 256 + fondo] -> mezcla al 50%
+    rgb_lut: Vec<u8>, // RGB555 -> indice mas cercano de la paleta 0
+}
+    impl PaletteT {
+        // Paleta generada (cubo de color 6x6x6 + rampa de grises) para cuando no hay WAD
+        pub fn builtin() -> Self {
+            let mut base = [0u32; 256];
+            for (i, color) in base.iter_mut().enumerate().take(216) {
+                let (r, g, b) = (i / 36, (i / 6) % 6, i % 6);
+                *color = rgb((r * 51) as u32, (g * 51) as u32, (b * 51) as u32);
+            }
+            for (i, color) in base.iter_mut().enumerate().skip(216) {
+                let v = ((i - 216) * 255 / 39) as u32;
+                *color = rgb(v, v, v);
+            }
+
+            let palettes = build_flash_palettes(&base);
+            let rgb_lut = build_rgb_lut(&base);
+            let colormaps = build_colormaps(&base, &rgb_lut);
+            let tranmap = build_tranmap(&base, &rgb_lut);
+            PaletteT { palettes, colormaps, tranmap, rgb_lut }
+        }
+
+        // Carga PLAYPAL y COLORMAP desde un WAD (si falta COLORMAP se genera)
+        pub fn from_wad(wad: &WadT) -> Result<Self, String> {
+            let playpal = read_playpal(wad)?;
+            let mut palettes: Vec<[u32; 256]> = playpal.chunks_exact(768).take(NUM_PALETTES).map(decode_palette).collect();
+            // Algunos WADs solo traen la paleta base
+            if palettes.len() < NUM_PALETTES {
+                palettes = build_flash_palettes(&palettes[0]);
+            }
+
+            let rgb_lut = build_rgb_lut(&palettes[0]);
+            let colormaps = match wad.lump("COLORMAP") {
+                Some(raw) if raw.len() >= NUM_LIGHT_MAPS * 256 => raw
+                    .chunks_exact(256)
+                    .take(NUM_COLORMAPS)
+                    .map(|c| {
+                        let mut map = [0u8; 256];
+                        map.copy_from_slice(c);
+                        map
+                    })
+                    .collect(),
+                _ => build_colormaps(&palettes[0], &rgb_lut),
+            };
+            let tranmap = build_tranmap(&palettes[0], &rgb_lut);
+            Ok(PaletteT { palettes, colormaps, tranmap, rgb_lut })
+        }
+
+        // Indice de la paleta base mas parecido a un color 0xRRGGBB
+        pub fn lookup(&self, color: u32) -> u8 {
+            self.rgb_lut[rgb555(color)]
+        }
+
+        // Indice final de un color visto con cierto nivel de luz
+        pub fn shade_index(&self, color: u32, light: i32) -> u8 {
+            self.colormaps[light_to_map(light)][self.lookup(color) as usize]
+        }
+
+        // Mezcla al 50% de dos indices (tabla de translucidez)
+        pub fn blend(&self, front: u8, back: u8) -> u8 {
+            self.tranmap[front as usize * 256 + back as usize]
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+fn read_playpal(wad: &WadT) -> Result<&[u8], String> {
+    let playpal = wad.lump("PLAYPAL").ok_or(format!("{}: falta el lump PLAYPAL", wad.path))?;
+    if playpal.len() < 768 {
+        return Err(format!("{}: PLAYPAL demasiado corto", wad.path));
+    }
+    Ok(playpal)
+}
+
+fn decode_palette(raw: &[u8]) -> [u32; 256] {
+    let mut pal = [0u32; 256];
+    for (i, color) in pal.iter_mut().enumerate() {
+        *color = rgb(raw[i * 3] as u32, raw[i * 3 + 1] as u32, raw[i * 3 + 2] as u32);
+    }
+    pal
+}
+
+// Paleta 0 del WAD, para convertir patches y texturas a color
+pub fn base_palette(wad: &WadT) -> Result<[u32; 256], String> {
+    read_playpal(wad).map(decode_palette)
+}
+
+pub fn rgb(r: u32, g: u32, b: u32) -> u32 {
+    (r.min(255) << 16) | (g.min(255) << 8) | b.min(255)
+}
+
+fn rgb555(color: u32) -> usize {
+    let r = (color >> 19) & 0x1F;
+    let g = (color >> 11) & 0x1F;
+    let b = (color >> 3) & 0x1F;
+    ((r << 10) | (g << 5) | b) as usize
+}
+
+// Nivel de luz (0..255) a colormap (0 = brillo total, 31 = oscuro)
+pub fn light_to_map(light: i32) -> usize {
+    ((255 - light.clamp(0, 255)) / 8) as usize
+}
+
+fn nearest(palette: &[u32; 256], r: i32, g: i32, b: i32) -> u8 {
+    let mut best = 0;
+    let mut best_dist = i32::MAX;
+    for (i, &c) in palette.iter().enumerate() {
+        let dr = ((c >> 16) & 0xFF) as i32 - r;
+        let dg = ((c >> 8) & 0xFF) as i32 - g;
+        let db = (c & 0xFF) as i32 - b;
+        let dist = dr * dr + dg * dg + db * db;
+        if dist < best_dist {
+            best_dist = dist;
+            best = i;
+            if dist == 0 {
+                break;
+            }
+        }
+    }
+    best as u8
+}
+
+fn build_rgb_lut(palette: &[u32; 256]) -> Vec<u8> {
+    let mut lut = vec![0u8; 32768];
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let r = ((i >> 10) & 0x1F) as i32 * 255 / 31;
+        let g = ((i >> 5) & 0x1F) as i32 * 255 / 31;
+        let b = (i & 0x1F) as i32 * 255 / 31;
+        *entry = nearest(palette, r, g, b);
+    }
+    lut
+}
+
+// 32 niveles de oscurecimiento, invulnerabilidad (grises invertidos) y negro
+fn build_colormaps(palette: &[u32; 256], rgb_lut: &[u8]) -> Vec<[u8; 256]> {
+    let mut maps = Vec::with_capacity(NUM_COLORMAPS);
+    for level in 0..NUM_LIGHT_MAPS as u32 {
+        let mut map = [0u8; 256];
+        for (i, entry) in map.iter_mut().enumerate() {
+            let c = palette[i];
+            let scale = NUM_LIGHT_MAPS as u32 - level;
+            let dimmed = rgb(((c >> 16) & 0xFF) * scale / 32, ((c >> 8) & 0xFF) * scale / 32, (c & 0xFF) * scale / 32);
+            *entry = rgb_lut[rgb555(dimmed)];
+        }
+        maps.push(map);
+    }
+
+    let mut invuln = [0u8; 256];
+    for (i, entry) in invuln.iter_mut().enumerate() {
+        let c = palette[i];
+        let grey = 255 - (((c >> 16) & 0xFF) + ((c >> 8) & 0xFF) + (c & 0xFF)) / 3;
+        *entry = rgb_lut[rgb555(rgb(grey, grey, grey))];
+    }
+    maps.push(invuln);
+    maps.push([rgb_lut[0]; 256]);
+    maps
+}
+
+// Tabla de translucidez al 50% (como TRANMAP de Boom)
+fn build_tranmap(palette: &[u32; 256], rgb_lut: &[u8]) -> Vec<u8> {
+    let mut map = vec![0u8; 256 * 256];
+    for front in 0..256 {
+        for back in 0..256 {
+            map[front * 256 + back] = rgb_lut[rgb555(blend_rgb(palette[front], palette[back], 128))];
+        }
+    }
+    map
+}
+
+// Mezcla dos colores 0xRRGGBB (alpha 255 = solo el primero)
+pub fn blend_rgb(front: u32, back: u32, alpha: u32) -> u32 {
+    let mix = |f: u32, b: u32| (f * alpha + b * (255 - alpha)) / 255;
+    rgb(
+        mix((front >> 16) & 0xFF, (back >> 16) & 0xFF),
+        mix((front >> 8) & 0xFF, (back >> 8) & 0xFF),
+        mix(front & 0xFF, back & 0xFF),
+    )
+}
+
+fn tint(color: u32, target: (u32, u32, u32), amount: u32) -> u32 {
+    let mix = |c: u32, t: u32| (c * (256 - amount) + t * amount) / 256;
+    rgb(mix((color >> 16) & 0xFF, target.0), mix((color >> 8) & 0xFF, target.1), mix(color & 0xFF, target.2))
+}
+
+// Tinte (color, cantidad sobre 256) de cada paleta especial, igual que las de PLAYPAL
+pub fn flash_tint(palette: usize) -> Option<((u32, u32, u32), u32)> {
+    match palette {
+        p if (DAMAGE_PALETTE_START..DAMAGE_PALETTE_START + NUM_DAMAGE_PALETTES).contains(&p) => {
+            Some(((255, 0, 0), (p - DAMAGE_PALETTE_START + 1) as u32 * 256 / 9))
+        }
+        p if (BONUS_PALETTE_START..BONUS_PALETTE_START + NUM_BONUS_PALETTES).contains(&p) => {
+            Some(((215, 186, 69), (p - BONUS_PALETTE_START + 1) as u32 * 32))
+        }
+        RADIATION_PALETTE => Some(((0, 255, 0), 32)),
+        _ => None,
+    }
+}
+
+pub fn apply_flash(color: u32, palette: usize) -> u32 {
+    match flash_tint(palette) {
+        Some((target, amount)) => tint(color, target, amount),
+        None => color,
+    }
+}
+
+fn build_flash_palettes(base: &[u32; 256]) -> Vec<[u32; 256]> {
+    (0..NUM_PALETTES)
+        .map(|p| {
+            let mut pal = *base;
+            for c in pal.iter_mut() {
+                *c = apply_flash(*c, p);
+            }
+            pal
+        })
+        .collect()
+}
+
+// Elige la paleta segun el daño y los items recogidos recientemente (como ST_doPaletteStuff)
+pub fn flash_palette(damage_count: i32, bonus_count: i32) -> usize {
+    if damage_count > 0 {
+        let level = ((damage_count + 7) >> 3).min(NUM_DAMAGE_PALETTES as i32) as usize;
+        DAMAGE_PALETTE_START + level - 1
+    } else if bonus_count > 0 {
+        let level = ((bonus_count + 7) >> 3).min(NUM_BONUS_PALETTES as i32) as usize;
+        BONUS_PALETTE_START + level - 1
+    } else {
+        0
+    }
+}
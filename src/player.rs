@@ -1,9 +1,37 @@
-use crate::typedefs;
+use crate::{game_state::GameStateT, typedefs, weapons::ArsenalT};
+
+// Altura de los ojos sobre el piso
+pub const EYE_HEIGHT: f64 = 41.0;
+pub const DEAD_VIEW_HEIGHT: f64 = 6.0; // La camara del jugador muerto queda casi en el piso
+pub const MAX_BOB: f64 = 16.0; // Balanceo maximo del arma al caminar (como MAXBOB)
+
+pub const NUM_KEYS: usize = 3;
+pub const KEY_NAMES: [&str; NUM_KEYS] = ["BLUE KEY", "YELLOW KEY", "RED KEY"];
+
+pub const MAX_HEALTH: i32 = 100;
+pub const GRAVITY: f64 = 1.0;          // Unidades por tic al cuadrado (como en Doom)
+pub const FALL_SAFE_SPEED: f64 = 12.0; // Velocidad de caida que no hace daño (unas 72 unidades de altura)
+pub const FALL_DAMAGE: f64 = 4.0;      // Daño por cada unidad por tic de mas al llegar al piso
+pub const FLOOR_DAMAGE_TICS: u64 = 32; // Cada cuanto lastiman los pisos peligrosos
+pub const RESPAWN_TICS: i32 = 35;      // Tics muerto antes de poder volver a empezar
 
 pub struct PlayerT {
     pub position: typedefs::Vec2T,
     pub z: f64,
+    pub vz: f64,           // Velocidad vertical al caer
     pub dir_angle: f64,
+    pub health: i32,
+    pub armor: i32,
+    pub armor_type: i32,   // 0 sin armadura, 1 verde (absorbe 1/3), 2 azul (absorbe 1/2)
+    pub keys: [bool; NUM_KEYS],
+    pub bob: f64,          // Amplitud del balanceo segun lo que se movio en el ultimo frame
+    pub damage_count: i32, // Tics restantes del destello rojo
+    pub bonus_count: i32,  // Tics restantes del destello de items
+    pub dead_tics: i32,    // Tics desde que murio
+    pub is_noclip: bool,   // Atraviesa paredes
+    pub is_god: bool,      // No recibe daño
+    pub is_flying: bool,   // Sin gravedad: sube y baja con las teclas
+    pub weapons: ArsenalT,
 }
 
 impl PlayerT {
@@ -12,7 +40,106 @@ impl PlayerT {
         PlayerT {
             position: typedefs::Vec2T { x, y },
             z,
+            vz: 0.0,
             dir_angle: angle,
+            health: MAX_HEALTH,
+            armor: 0,
+            armor_type: 0,
+            keys: [false; NUM_KEYS],
+            bob: 0.0,
+            damage_count: 0,
+            bonus_count: 0,
+            dead_tics: 0,
+            is_noclip: false,
+            is_god: false,
+            is_flying: false,
+            weapons: ArsenalT::new(),
+        }
+    }
+}
+
+pub fn is_dead(player: &PlayerT) -> bool {
+    player.health <= 0
+}
+
+// Al pasar de nivel se conservan salud, armadura, armas y municion, pero no las llaves
+pub fn finish_level(player: &mut PlayerT) {
+    player.keys = [false; NUM_KEYS];
+    player.vz = 0.0;
+    player.bob = 0.0;
+    player.damage_count = 0;
+    player.bonus_count = 0;
+}
+
+// Gravedad, daño por caida y la camara que baja al morir
+fn fall(state: &GameStateT, player: &mut PlayerT) {
+    let Some(sector) = state.sector_at(player.position.x, player.position.y) else { return; };
+    let floor = state.sectors[sector].floor_z();
+    let dead = is_dead(player);
+    let rest = floor + if dead { DEAD_VIEW_HEIGHT } else { EYE_HEIGHT };
+    if player.z > rest {
+        if dead && player.z <= floor + EYE_HEIGHT {
+            // Ya en el piso: se desploma de a una unidad por tic
+            player.z = (player.z - 1.0).max(rest);
+            return;
+        }
+        if player.is_flying || player.is_noclip {
+            player.vz = 0.0;
+            return;
+        }
+        player.vz -= GRAVITY;
+        player.z += player.vz;
+    }
+    if player.z <= rest {
+        if player.vz < -FALL_SAFE_SPEED {
+            damage(player, ((-player.vz - FALL_SAFE_SPEED) * FALL_DAMAGE) as i32);
         }
+        player.vz = 0.0;
+        player.z = player.z.max(rest);
     }
-}
\ No newline at end of file
+}
+
+// Avanza un tic los contadores del jugador
+pub fn tick(state: &GameStateT, player: &mut PlayerT) {
+    if player.damage_count > 0 {
+        player.damage_count -= 1;
+    }
+    if player.bonus_count > 0 {
+        player.bonus_count -= 1;
+    }
+    fall(state, player);
+    if is_dead(player) {
+        player.dead_tics += 1;
+        return;
+    }
+    // Pisos peligrosos: solo con los pies apoyados
+    if let Some(sector) = state.sector_at(player.position.x, player.position.y) {
+        let sector = &state.sectors[sector];
+        let on_floor = player.z - EYE_HEIGHT <= sector.floor_z();
+        if sector.damage > 0 && on_floor && state.tic.is_multiple_of(FLOOR_DAMAGE_TICS) {
+            damage(player, sector.damage);
+        }
+    }
+}
+
+// Recibe daño: la armadura absorbe una parte y la salud el resto (el modo dios lo ignora)
+pub fn damage(player: &mut PlayerT, amount: i32) {
+    if player.is_god || is_dead(player) || amount <= 0 {
+        return;
+    }
+    let mut amount = amount;
+    if player.armor_type > 0 {
+        let saved = (if player.armor_type == 1 { amount / 3 } else { amount / 2 }).min(player.armor);
+        player.armor -= saved;
+        if player.armor == 0 {
+            player.armor_type = 0;
+        }
+        amount -= saved;
+    }
+    player.health = (player.health - amount).max(0);
+    player.damage_count = (player.damage_count + amount).min(100);
+    if is_dead(player) {
+        player.dead_tics = 0;
+        player.weapons.is_firing = false;
+    }
+}
@@ -1,5 +1,15 @@
+use std::collections::VecDeque;
 use sdl2::{video::{Window, WindowContext}, VideoSubsystem, render::{Canvas, Texture, TextureCreator}, pixels::PixelFormatEnum};
-use crate::{game_state, player, typedefs};
+use crate::{game_state, lighting, movers, palette, specials, player, sprites::SpriteThingT, textures::{self, TextureT}, typedefs};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const DEFAULT_FOV: f64 = std::f64::consts::FRAC_PI_2; // 90 grados horizontales
+pub const NEAR_PLANE: f64 = 1.0;
+pub const MAX_PORTAL_QUEUE: usize = 256; // Evita ciclos infinitos entre portales
+pub const SKY_REPEATS: f64 = 4.0; // Veces que se repite la textura del cielo en 360 grados
+pub const SKY_VIEW_ROWS: f64 = 200.0; // Filas de pantalla (a 320x200) que abarca el cielo
+pub const SPRITE_NEAR: f64 = 4.0; // Los sprites mas cerca que esto no se dibujan
+pub const VIEW_CEIL_GAP: f64 = 4.0; // Distancia minima de los ojos al techo
 
 
 ///////////////////////////////// STRUCTS /////////////////////////////////
@@ -16,6 +26,11 @@ pub struct WallT {
     pub portal_top_height: f64,
     pub portal_bot_height: f64,
     pub is_portal: bool,
+    pub neighbor: Option<usize>, // Sector del otro lado del portal
+    pub mid_texture: Option<String>, // Textura enmascarada del medio (solo portales)
+    pub alpha: u8, // 255 opaca, menos es translucida
+    pub blocks_sound: bool, // Cuenta para cortar el ruido (ver ai::noise_alert)
+    pub special: Option<specials::SpecialT>, // Interruptor, linea que se cruza o a la que se dispara
 }
     impl WallT {
         pub fn new(x1:f64, y1:f64, x2:f64, y2:f64, portal_top_height:f64, portal_bot_height:f64, is_portal:bool) -> Self {
@@ -25,8 +40,17 @@ pub struct WallT {
                 portal_top_height: portal_top_height, 
                 portal_bot_height: portal_bot_height, 
                 is_portal: is_portal,
+                neighbor: None,
+                mid_texture: None,
+                alpha: 255,
+                blocks_sound: false,
+                special: None,
             }
         }
+
+        pub fn length(&self) -> f64 {
+            (self.b.x - self.a.x).hypot(self.b.y - self.a.y)
+        }
     }
 
 #[derive(Clone)]
@@ -40,6 +64,13 @@ pub struct SectorT {
     pub color: u32,
     pub floor_clr: u32,
     pub ceil_clr: u32,
+    pub light: i32, // Nivel de luz actual (0..255)
+    pub light_fx: lighting::LightFxT,
+    pub is_sky: bool, // Techo abierto: se dibuja el cielo en vez de ceil_clr
+    pub damage: i32,  // Salud que pierde el jugador parado en el piso cada 32 tics
+    pub mover: movers::MoverT, // Puerta, ascensor o trituradora (la altura cambia mientras se juega)
+    pub tag: i32,              // Para los specials de las paredes (0 = sin tag)
+    pub is_secret: bool,       // Cuenta como secreto la primera vez que se entra (despues se apaga)
 
     pub portals_floorx_ylut: RPlaneT,
     pub portals_ceilx_ylut: RPlaneT,
@@ -58,6 +89,13 @@ pub struct SectorT {
                 color,
                 floor_clr,
                 ceil_clr,
+                light: 255,
+                light_fx: lighting::LightFxT::new(),
+                is_sky: false,
+                damage: 0,
+                mover: movers::MoverT::new(),
+                tag: 0,
+                is_secret: false,
     
                 portals_floorx_ylut: RPlaneT { t: [0; 1024], b: [0; 1024] },
                 portals_ceilx_ylut: RPlaneT { t: [0; 1024], b: [0; 1024] },
@@ -67,10 +105,31 @@ pub struct SectorT {
         }
 
         pub fn add_wall(&mut self, wall: WallT) {
-            if self.num_walls < 10 {
-                self.walls[self.num_walls as usize] = wall;
-                self.num_walls += 1;
+            self.walls.push(wall);
+            self.num_walls += 1;
+        }
+
+        // Altura absoluta del piso y del techo
+        pub fn floor_z(&self) -> f64 {
+            self.elevation as f64
+        }
+        pub fn ceil_z(&self) -> f64 {
+            (self.elevation + self.height) as f64
+        }
+
+        // Test de punto dentro del poligono (cuenta cruces de un rayo horizontal)
+        pub fn contains(&self, x: f64, y: f64) -> bool {
+            let mut inside = false;
+            for wall in &self.walls {
+                let (a, b) = (&wall.a, &wall.b);
+                if (a.y > y) != (b.y > y) {
+                    let cross_x = a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y);
+                    if x < cross_x {
+                        inside = !inside;
+                    }
+                }
             }
+            inside
         }
     }
 
@@ -84,6 +143,12 @@ pub struct SectorsQueueT {
 pub struct Screen { 
     pub screen_buffer: Vec<u32>, // pixeles
     pub screen_buffer_size: usize, // cant total de pixeles
+    pub width: u32,
+    pub height: u32,
+    pub index_buffer: Vec<u8>, // indices de paleta (modo 8 bits)
+    pub is_indexed: bool,
+    pub palette: Option<palette::PaletteT>,
+    pub active_palette: usize, // 0 normal, el resto son los destellos de daño/items
 }
 
 impl Screen {
@@ -92,6 +157,12 @@ impl Screen {
         Screen {
             screen_buffer: Vec::new(),
             screen_buffer_size: 0,
+            width: 0,
+            height: 0,
+            index_buffer: Vec::new(),
+            is_indexed: false,
+            palette: None,
+            active_palette: 0,
         }
     }
 
@@ -99,6 +170,9 @@ impl Screen {
     pub fn init_screen(&mut self, video_subsystem: &VideoSubsystem, w: u32, h: u32) -> Canvas<Window> {
         self.screen_buffer_size = (w * h) as usize; // Width*Height (Cantidad de pixeles de la ventana)
         self.screen_buffer = vec![0; self.screen_buffer_size]; // Inicia todo el vector en 0 con tamaño screen_buffer_size
+        self.index_buffer = vec![0; self.screen_buffer_size];
+        self.width = w;
+        self.height = h;
         
         // Iniciar un WindowBuilder para crear una ventana
         let window: Window = video_subsystem.window("Engine", w, h)
@@ -114,11 +188,13 @@ impl Screen {
     // Crear y cargar la textura con los datos de screen_buffer
     pub fn render(&mut self, canvas: &mut Canvas<Window>, w: u32, h: u32) {
 
+        self.resolve_palette();
+
         // Iniciar el texture_creator para poder usar el canvas
         let texture_creator: TextureCreator<WindowContext> = canvas.texture_creator();
 
-        // Crear textura RGBA32 (32 bits por pixel: 8 bits por canal rojo, verde, azul y alfa).
-        match texture_creator.create_texture_streaming(PixelFormatEnum::RGBA32, w, h) {
+        // Crear textura RGB888 (32 bits por pixel, 0x00RRGGBB, el byte alto se ignora).
+        match texture_creator.create_texture_streaming(PixelFormatEnum::RGB888, w, h) {
             Ok(mut texture) => {
                 // Actualizar la textura con el screen_buffer
                 texture.update(None, unsafe { &self.screen_buffer.align_to::<u8>().1 }, (w * 4) as usize).unwrap();
@@ -134,6 +210,177 @@ impl Screen {
     fn shutdown(&self) {
         eprintln!("Shutting down screen resources.");
     }
+
+    // Activa o desactiva el modo 8 bits (sin WAD usa la paleta generada)
+    pub fn set_indexed(&mut self, indexed: bool) {
+        if indexed && self.palette.is_none() {
+            self.palette = Some(palette::PaletteT::builtin());
+        }
+        self.is_indexed = indexed;
+    }
+
+    pub fn clear(&mut self) {
+        self.screen_buffer.fill(0);
+        self.index_buffer.fill(0);
+    }
+
+    // Cambia el tamaño del framebuffer (la textura se estira a la ventana al presentar)
+    pub fn resize(&mut self, w: u32, h: u32) {
+        self.screen_buffer_size = (w * h) as usize;
+        self.screen_buffer = vec![0; self.screen_buffer_size];
+        self.index_buffer = vec![0; self.screen_buffer_size];
+        self.width = w;
+        self.height = h;
+    }
+
+    // Oscurece un rectangulo mezclando un color encima (fondos de consola y menus)
+    pub fn blend_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: u32, alpha: u8) {
+        let x0 = x.max(0);
+        let y0 = y.max(0);
+        let x1 = (x + w).min(self.width as i32);
+        let y1 = (y + h).min(self.height as i32);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                self.plot_alpha(py as usize * self.width as usize + px as usize, color, 255, alpha);
+            }
+        }
+    }
+
+    // Guarda lo ultimo que se presento como BMP de 24 bits
+    pub fn save_bmp(&self, path: &str) -> Result<(), String> {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let row_size = (w * 3).div_ceil(4) * 4;
+        let data_size = row_size * h;
+        let mut bytes: Vec<u8> = Vec::with_capacity(54 + data_size);
+        bytes.extend_from_slice(b"BM");
+        bytes.extend_from_slice(&(54 + data_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&54u32.to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&(w as i32).to_le_bytes());
+        bytes.extend_from_slice(&(h as i32).to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&24u16.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 24]);
+        // Las filas van de abajo hacia arriba, en BGR
+        for y in (0..h).rev() {
+            for &color in &self.screen_buffer[y * w..(y + 1) * w] {
+                bytes.extend_from_slice(&[color as u8, (color >> 8) as u8, (color >> 16) as u8]);
+            }
+            bytes.resize(bytes.len() + row_size - w * 3, 0);
+        }
+        std::fs::write(path, bytes).map_err(|e| format!("no se pudo escribir '{}': {}", path, e))
+    }
+
+    // Pinta toda la pantalla de un color
+    pub fn fill(&mut self, color: u32) {
+        match &self.palette {
+            Some(pal) if self.is_indexed => self.index_buffer.fill(pal.lookup(color)),
+            _ => self.screen_buffer.fill(color),
+        }
+    }
+
+    // Escribe un pixel de un color 0xRRGGBB visto con cierto nivel de luz
+    pub fn plot(&mut self, offset: usize, color: u32, light: i32) {
+        match &self.palette {
+            Some(pal) if self.is_indexed => self.index_buffer[offset] = pal.shade_index(color, light),
+            _ => self.screen_buffer[offset] = lighting::shade(color, light),
+        }
+    }
+
+    // Pixel sin sombrear (HUD, textos, mapa)
+    pub fn put_pixel(&mut self, x: i32, y: i32, color: u32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        self.plot(y as usize * self.width as usize + x as usize, color, 255);
+    }
+
+    // Linea entre dos puntos (Bresenham), recortada antes a la pantalla (Liang-Barsky)
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
+        let (w, h) = (self.width as f64 - 1.0, self.height as f64 - 1.0);
+        let (fx0, fy0) = (x0 as f64, y0 as f64);
+        let (dx, dy) = (x1 as f64 - fx0, y1 as f64 - fy0);
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+        for (p, q) in [(-dx, fx0), (dx, w - fx0), (-dy, fy0), (dy, h - fy0)] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return;
+                }
+            } else {
+                let r = q / p;
+                if p < 0.0 { t0 = t0.max(r); } else { t1 = t1.min(r); }
+            }
+        }
+        if t0 > t1 {
+            return;
+        }
+
+        let (mut x, mut y) = ((fx0 + dx * t0).round() as i32, (fy0 + dy * t0).round() as i32);
+        let (xe, ye) = ((fx0 + dx * t1).round() as i32, (fy0 + dy * t1).round() as i32);
+        let (sx, sy) = ((xe - x).signum(), (ye - y).signum());
+        let (adx, ady) = ((xe - x).abs(), -(ye - y).abs());
+        let mut err = adx + ady;
+        loop {
+            self.put_pixel(x, y, color);
+            if x == xe && y == ye {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= ady { err += ady; x += sx; }
+            if e2 <= adx { err += adx; y += sy; }
+        }
+    }
+
+    // Como plot, pero mezclando con lo que ya hay (en 8 bits usa la tabla al 50%)
+    pub fn plot_alpha(&mut self, offset: usize, color: u32, light: i32, alpha: u8) {
+        if alpha == 255 {
+            self.plot(offset, color, light);
+            return;
+        }
+        match &self.palette {
+            Some(pal) if self.is_indexed => {
+                let front = pal.shade_index(color, light);
+                self.index_buffer[offset] = pal.blend(front, self.index_buffer[offset]);
+            }
+            _ => {
+                let front = lighting::shade(color, light);
+                self.screen_buffer[offset] = palette::blend_rgb(front, self.screen_buffer[offset], alpha as u32);
+            }
+        }
+    }
+
+    // Dibuja una linea vertical [y1, y2] en la columna x (recortada a la pantalla)
+    pub fn vline(&mut self, x: i32, y1: i32, y2: i32, color: u32, light: i32) {
+        if x < 0 || x >= self.width as i32 {
+            return;
+        }
+        let y1 = y1.max(0);
+        let y2 = y2.min(self.height as i32 - 1);
+        let w = self.width as usize;
+        for y in y1..=y2 {
+            self.plot(y as usize * w + x as usize, color, light);
+        }
+    }
+
+    // Convierte los indices a color con la paleta activa (o aplica el destello en 32 bits)
+    pub fn resolve_palette(&mut self) {
+        match &self.palette {
+            Some(pal) if self.is_indexed => {
+                let colors = &pal.palettes[self.active_palette.min(pal.palettes.len() - 1)];
+                for (dst, &index) in self.screen_buffer.iter_mut().zip(self.index_buffer.iter()) {
+                    *dst = colors[index as usize];
+                }
+            }
+            _ => {
+                if self.active_palette != 0 {
+                    for dst in self.screen_buffer.iter_mut() {
+                        *dst = palette::apply_flash(*dst, self.active_palette);
+                    }
+                }
+            }
+        }
+    }
 }
 
 ///////////////////////////////// FUNCIONES  /////////////////////////////////
@@ -164,34 +411,412 @@ pub fn update_screen(canvas: &mut Canvas<Window>, texture: &mut Texture, screen_
     canvas.present();
 }
 
-pub fn render(player: &player::PlayerT, game_state: &game_state::GameStateT){
-    let is_debug_mode = game_state.is_debug_mode;
-    //update_screen(canvas, texture, screen_buffer, width, height);
-}
-
-pub fn draw_walls(game_state: &game_state::GameStateT, canvas: &mut Canvas<Window>) {
-    if !game_state.sectors.is_empty() {
-        // Iterar sobre cada sector
-        for sector in &game_state.sectors {
-            // Iterar sobre las paredes de cada sector
-            for wall in &sector.walls {
-                // Calcular la proyección de las paredes en la pantalla
-                // Para la simplicidad, consideramos que las paredes son líneas
-                let x1 = wall.a.x as i32;
-                let y1 = wall.a.y as i32;
-                let x2 = wall.b.x as i32;
-                let y2 = wall.b.y as i32;
-                
-                // Dibujar la pared
-                canvas.set_draw_color(sdl2::pixels::Color::RGB(255, 255, 255)); // Blanco para las paredes
-                if let Err(e) = canvas.draw_line((x1, y1), (x2, y2)) {
-                    eprintln!("Error al dibujar la línea: {}", e);
-                }
+///////////////////////////////// RENDER 3D  /////////////////////////////////
+// Resultado de un frame que le interesa al resto del juego
+pub struct RenderInfoT {
+    pub drawn_walls: Vec<(usize, usize)>, // (sector, pared) con al menos una columna visible
+    pub sectors_visited: usize,
+    pub walls_visited: usize,
+    pub clip_log: Vec<Vec<ClipEntryT>>,
+}
+
+struct RenderItemT {
+    sector: usize,
+    x0: i32,
+    x1: i32,
+}
+
+// Columna de una textura enmascarada, guardada para dibujarla despues de la geometria solida
+struct MaskedColumnT {
+    x: i32,
+    iz: f64,
+    u: f64,
+    ytop: i32, // Ventana visible a traves del portal en ese momento
+    ybot: i32,
+    light: i32,
+}
+
+struct MaskedSegT<'a> {
+    texture: &'a TextureT,
+    top_z: f64, // La textura cuelga desde el borde superior de la abertura
+    alpha: u8,
+    depth: f64,
+    columns: Vec<MaskedColumnT>,
+}
+
+// Sprite ya proyectado a pantalla
+struct VisSpriteT<'a> {
+    texture: &'a TextureT,
+    flip: bool,
+    x1: f64,    // Borde izquierdo en pantalla
+    top_y: f64, // Borde superior en pantalla
+    scale: f64,
+    depth: f64,
+    light: i32,
+}
+
+// Lo que se dibuja despues de la geometria solida, ordenado de atras hacia adelante
+enum MaskedItemT<'a> {
+    Wall(MaskedSegT<'a>),
+    Sprite(VisSpriteT<'a>),
+}
+    impl MaskedItemT<'_> {
+        fn depth(&self) -> f64 {
+            match self {
+                MaskedItemT::Wall(seg) => seg.depth,
+                MaskedItemT::Sprite(spr) => spr.depth,
             }
         }
+    }
+
+// Cada vez que una pared achica la ventana de una columna se guarda a que profundidad
+#[derive(Clone, Copy)]
+pub struct ClipEntryT {
+    pub depth: f64,
+    pub ytop: i32,
+    pub ybot: i32,
+}
+
+// Ventana visible en una columna para algo que esta a cierta profundidad
+fn clip_window(log: &[ClipEntryT], depth: f64, height: i32) -> (i32, i32) {
+    log.iter()
+        .filter(|e| e.depth < depth)
+        .fold((0, height - 1), |(top, bot), e| (top.max(e.ytop), bot.min(e.ybot)))
+}
+
+// Parametros de la camara para el frame actual
+pub struct ViewT {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub sin_a: f64,
+    pub cos_a: f64,
+    pub width: i32,
+    pub height: i32,
+    pub half_w: f64,
+    pub half_h: f64,
+    pub focal: f64,
+}
+    impl ViewT {
+        pub fn new(player: &player::PlayerT, width: u32, height: u32, fov: f64) -> Self {
+            let (sin_a, cos_a) = player.dir_angle.sin_cos();
+            let half_w = width as f64 / 2.0;
+            ViewT {
+                x: player.position.x,
+                y: player.position.y,
+                z: player.z,
+                sin_a,
+                cos_a,
+                width: width as i32,
+                height: height as i32,
+                half_w,
+                half_h: height as f64 / 2.0,
+                focal: half_w / (fov / 2.0).tan(),
+            }
+        }
+
+        // Transforma un punto del mundo a espacio de vista (x a la derecha, z hacia adelante)
+        pub fn to_camera(&self, p: &typedefs::Vec2T) -> (f64, f64) {
+            let dx = p.x - self.x;
+            let dy = p.y - self.y;
+            (dx * self.sin_a - dy * self.cos_a, dx * self.cos_a + dy * self.sin_a)
+        }
+
+        // Angulo del mundo que se ve en la columna x
+        pub fn column_angle(&self, x: i32, dir_angle: f64) -> f64 {
+            dir_angle - ((x as f64 + 0.5 - self.half_w) / self.focal).atan()
+        }
+
+        // Profundidad de la fila y para un plano a cierta altura relativa al ojo
+        pub fn plane_depth(&self, rel_height: f64, y: i32) -> f64 {
+            let dy = (y as f64 + 0.5 - self.half_h).abs().max(0.5);
+            (rel_height * self.focal / dy).abs()
+        }
+    }
+
+// Pinta un tramo vertical del piso o del techo de un sector, sombreado por fila
+fn draw_flat(screen: &mut Screen, view: &ViewT, x: i32, y1: i32, y2: i32, sector: &SectorT, is_ceiling: bool) {
+    let (color, rel_height) = if is_ceiling {
+        (sector.ceil_clr, sector.ceil_z() - view.z)
     } else {
-        eprintln!("No hay sectores en el game state");
+        (sector.floor_clr, sector.floor_z() - view.z)
+    };
+    let y1 = y1.max(0);
+    let y2 = y2.min(screen.height as i32 - 1);
+    let w = screen.width as usize;
+    for y in y1..=y2 {
+        let depth = view.plane_depth(rel_height, y);
+        screen.plot(y as usize * w + x as usize, color, lighting::light_at(sector.light, depth));
+    }
+}
+
+// Pinta el cielo en un tramo de columna: la textura gira con el angulo de vista y no depende de la altura
+fn draw_sky(screen: &mut Screen, view: &ViewT, sky: &TextureT, x: i32, y1: i32, y2: i32, dir_angle: f64) {
+    let y1 = y1.max(0);
+    let y2 = y2.min(screen.height as i32 - 1);
+    if y1 > y2 {
+        return;
+    }
+    let angle = view.column_angle(x, dir_angle);
+    let u = (-angle / std::f64::consts::TAU * sky.width as f64 * SKY_REPEATS).rem_euclid(sky.width as f64);
+    let column = sky.column(u as usize);
+    let row_scale = SKY_VIEW_ROWS / view.height as f64;
+    let w = screen.width as usize;
+    for y in y1..=y2 {
+        let v = ((y as f64 - view.half_h) * row_scale + SKY_VIEW_ROWS / 2.0).clamp(0.0, (sky.height - 1) as f64);
+        let color = column[v as usize];
+        if color != textures::TRANSPARENT {
+            screen.plot(y as usize * w + x as usize, color, 255);
+        }
+    }
+}
+
+// Textura del medio de un portal, recortada a la abertura que se veia en cada columna
+fn draw_masked_seg(screen: &mut Screen, view: &ViewT, seg: &MaskedSegT) {
+    let w = screen.width as usize;
+    for col in &seg.columns {
+        let column = seg.texture.column(col.u.rem_euclid(seg.texture.width as f64) as usize);
+        let scale = view.focal * col.iz;
+        let y_top = (view.half_h - (seg.top_z - view.z) * scale).ceil() as i32;
+        let y_bot = (view.half_h - (seg.top_z - seg.texture.height as f64 - view.z) * scale).ceil() as i32 - 1;
+        for y in y_top.max(col.ytop)..=y_bot.min(col.ybot) {
+            let v = (seg.top_z - view.z - (view.half_h - y as f64 - 0.5) / scale).max(0.0) as usize;
+            let Some(&color) = column.get(v) else { continue; };
+            if color != textures::TRANSPARENT {
+                screen.plot_alpha(y as usize * w + col.x as usize, color, col.light, seg.alpha);
+            }
+        }
+    }
+}
+
+// Proyecta un objeto del mundo; None si queda detras de la camara o no tiene imagen
+fn project_sprite<'a>(view: &ViewT, thing: &SpriteThingT, game_state: &'a game_state::GameStateT, light: i32) -> Option<VisSpriteT<'a>> {
+    let (tx, tz) = view.to_camera(&thing.position);
+    if tz < SPRITE_NEAR {
+        return None;
+    }
+    let lump = game_state.sprites.select(thing, view.x, view.y)?;
+    let texture = game_state.textures.get(&lump.texture)?;
+
+    let scale = view.focal / tz;
+    let left_offset = if lump.flip { texture.width as i32 - texture.left_offset } else { texture.left_offset };
+    let x1 = view.half_w + tx * scale - left_offset as f64 * scale;
+    if x1 >= view.width as f64 || x1 + texture.width as f64 * scale <= 0.0 {
+        return None;
+    }
+    Some(VisSpriteT {
+        texture,
+        flip: lump.flip,
+        x1,
+        top_y: view.half_h - (thing.z + texture.top_offset as f64 - view.z) * scale,
+        scale,
+        depth: tz,
+        light: lighting::light_at(light, tz),
+    })
+}
+
+// Sprite escalado, recortado columna por columna contra las paredes que tiene delante
+fn draw_sprite(screen: &mut Screen, spr: &VisSpriteT, clip_log: &[Vec<ClipEntryT>]) {
+    let w = screen.width as i32;
+    let h = screen.height as i32;
+    let tex = spr.texture;
+    let x_start = (spr.x1.ceil() as i32).max(0);
+    let x_end = ((spr.x1 + tex.width as f64 * spr.scale).ceil() as i32 - 1).min(w - 1);
+    let y_start = spr.top_y.ceil() as i32;
+    let y_end = (spr.top_y + tex.height as f64 * spr.scale).ceil() as i32 - 1;
+
+    for x in x_start..=x_end {
+        let mut tc = (((x as f64 + 0.5 - spr.x1) / spr.scale) as usize).min(tex.width - 1);
+        if spr.flip {
+            tc = tex.width - 1 - tc;
+        }
+        let column = tex.column(tc);
+        let (top, bot) = clip_window(&clip_log[x as usize], spr.depth, h);
+        for y in y_start.max(top)..=y_end.min(bot) {
+            let v = (((y as f64 + 0.5 - spr.top_y) / spr.scale) as usize).min(tex.height - 1);
+            let color = column[v];
+            if color != textures::TRANSPARENT {
+                screen.plot(y as usize * w as usize + x as usize, color, spr.light);
+            }
+        }
+    }
+}
+
+// Renderiza la vista 3D recorriendo los sectores de adelante hacia atras a traves de los portales
+pub fn render(screen: &mut Screen, player: &player::PlayerT, game_state: &game_state::GameStateT) -> RenderInfoT {
+    screen.clear();
+    let mut info = RenderInfoT { drawn_walls: Vec::new(), sectors_visited: 0, walls_visited: 0, clip_log: Vec::new() };
+
+    let Some(start) = game_state.sector_at(player.position.x, player.position.y) else {
+        return info;
+    };
+
+    let mut view = ViewT::new(player, screen.width, screen.height, game_state.fov);
+    // Como en Doom la vista queda bajo el techo aunque este baje (trituradoras)
+    view.z = view.z.min(game_state.sectors[start].ceil_z() - VIEW_CEIL_GAP);
+    let (w, h) = (view.width, view.height);
+    let sky = game_state.textures.get(textures::SKY_TEXTURE);
+
+    // Ventana vertical todavia libre por columna
+    let mut ytop: Vec<i32> = vec![0; w as usize];
+    let mut ybot: Vec<i32> = vec![h - 1; w as usize];
+
+    let mut clip_log: Vec<Vec<ClipEntryT>> = vec![Vec::new(); w as usize];
+    let mut visited: Vec<bool> = vec![false; game_state.sectors.len()];
+    let mut masked: Vec<MaskedItemT> = Vec::new();
+    let mut queue: VecDeque<RenderItemT> = VecDeque::new();
+    queue.push_back(RenderItemT { sector: start, x0: 0, x1: w - 1 });
+    let mut processed: usize = 0;
+
+    while let Some(item) = queue.pop_front() {
+        processed += 1;
+        if processed > MAX_PORTAL_QUEUE {
+            break;
+        }
+        let sector = &game_state.sectors[item.sector];
+        let floor_rel = sector.floor_z() - view.z;
+        visited[item.sector] = true;
+
+        info.sectors_visited += 1;
+        for (wall_index, wall) in sector.walls.iter().enumerate() {
+            info.walls_visited += 1;
+            let (mut ax, mut az) = view.to_camera(&wall.a);
+            let (mut bx, mut bz) = view.to_camera(&wall.b);
+            // Coordenada de textura a lo largo de la pared
+            let (mut ua, mut ub) = (0.0, wall.length());
+
+            // Recorte contra el plano cercano
+            if az < NEAR_PLANE && bz < NEAR_PLANE {
+                continue;
+            }
+            if az < NEAR_PLANE {
+                let t = (NEAR_PLANE - az) / (bz - az);
+                ax += (bx - ax) * t;
+                az = NEAR_PLANE;
+                ua = ub * t;
+            } else if bz < NEAR_PLANE {
+                let t = (NEAR_PLANE - bz) / (az - bz);
+                bx += (ax - bx) * t;
+                bz = NEAR_PLANE;
+                ub *= 1.0 - t;
+            }
+
+            // Proyeccion horizontal; si queda al reves es la cara trasera
+            let sx1 = view.half_w + ax * view.focal / az;
+            let sx2 = view.half_w + bx * view.focal / bz;
+            if sx1 >= sx2 {
+                continue;
+            }
+            let x_start = (sx1.ceil() as i32).max(item.x0);
+            let x_end = (sx2.ceil() as i32 - 1).min(item.x1);
+            if x_start > x_end {
+                continue;
+            }
+
+            let neighbor = wall.neighbor.map(|n| &game_state.sectors[n]);
+            // Entre dos sectores con cielo no hay pared superior: el techo baja hasta el del vecino
+            let ceil_rel = match neighbor {
+                Some(next) if sector.is_sky && next.is_sky => next.ceil_z() - view.z,
+                _ => sector.ceil_z() - view.z,
+            };
+            let mut masked_seg = match (neighbor, &wall.mid_texture) {
+                (Some(next), Some(name)) => game_state.textures.get(name).map(|texture| MaskedSegT {
+                    texture,
+                    top_z: sector.ceil_z().min(next.ceil_z()),
+                    alpha: wall.alpha,
+                    depth: 0.0,
+                    columns: Vec::new(),
+                }),
+                _ => None,
+            };
+            // Contraste falso como en Doom: paredes en X mas oscuras, en Y mas claras
+            let contrast = if wall.a.y == wall.b.y { -16 } else if wall.a.x == wall.b.x { 16 } else { 0 };
+            let mut is_drawn = false;
+
+            for x in x_start..=x_end {
+                let xi = x as usize;
+                if ytop[xi] > ybot[xi] {
+                    continue;
+                }
+                is_drawn = true;
+                let t = ((x as f64 + 0.5 - sx1) / (sx2 - sx1)).clamp(0.0, 1.0);
+                let iz = (1.0 / az) + (1.0 / bz - 1.0 / az) * t;
+                let depth = 1.0 / iz;
+                let wall_light = lighting::light_at(sector.light + contrast, depth);
+                let color = sector.color;
+
+                let yc = view.half_h - ceil_rel * view.focal * iz;
+                let yf = view.half_h - floor_rel * view.focal * iz;
+                let cya = (yc.ceil() as i32).clamp(ytop[xi], ybot[xi] + 1);
+                let cyb = (yf.floor() as i32).clamp(ytop[xi] - 1, ybot[xi]).max(cya - 1);
+
+                // Techo (o cielo) y piso del sector
+                match sky {
+                    Some(sky) if sector.is_sky => draw_sky(screen, &view, sky, x, ytop[xi], cya - 1, player.dir_angle),
+                    _ => draw_flat(screen, &view, x, ytop[xi], cya - 1, sector, true),
+                }
+                draw_flat(screen, &view, x, cyb + 1, ybot[xi], sector, false);
+
+                match neighbor {
+                    Some(next) => {
+                        let nyc = view.half_h - (next.ceil_z() - view.z) * view.focal * iz;
+                        let nyf = view.half_h - (next.floor_z() - view.z) * view.focal * iz;
+                        let cnya = (nyc.ceil() as i32).clamp(cya, cyb + 1);
+                        let cnyb = (nyf.floor() as i32).clamp(cya - 1, cyb);
+
+                        // Escalon superior e inferior
+                        screen.vline(x, cya, cnya - 1, color, wall_light);
+                        screen.vline(x, cnyb + 1, cyb, color, wall_light);
+
+                        ytop[xi] = cnya.max(ytop[xi]);
+                        ybot[xi] = cnyb.min(ybot[xi]);
+                        clip_log[xi].push(ClipEntryT { depth, ytop: ytop[xi], ybot: ybot[xi] });
+
+                        if let Some(seg) = masked_seg.as_mut() && ytop[xi] <= ybot[xi] {
+                            let u = (ua / az + (ub / bz - ua / az) * t) / iz;
+                            seg.depth = seg.depth.max(depth);
+                            seg.columns.push(MaskedColumnT { x, iz, u, ytop: ytop[xi], ybot: ybot[xi], light: wall_light });
+                        }
+                    }
+                    None => {
+                        screen.vline(x, cya, cyb, color, wall_light);
+                        ytop[xi] = h;
+                        ybot[xi] = -1;
+                        clip_log[xi].push(ClipEntryT { depth, ytop: h, ybot: -1 });
+                    }
+                }
+            }
+
+            if is_drawn {
+                info.drawn_walls.push((item.sector, wall_index));
+            }
+            if let Some(n) = wall.neighbor {
+                queue.push_back(RenderItemT { sector: n, x0: x_start, x1: x_end });
+            }
+            if let Some(seg) = masked_seg && !seg.columns.is_empty() {
+                masked.push(MaskedItemT::Wall(seg));
+            }
+        }
+    }
+
+    // Objetos de los sectores que se llegaron a ver
+    for (index, _) in visited.iter().enumerate().filter(|(_, seen)| **seen) {
+        for entity in game_state.entities.in_sector(index) {
+            if let Some(spr) = project_sprite(&view, &entity.thing, game_state, game_state.sectors[index].light) {
+                masked.push(MaskedItemT::Sprite(spr));
+            }
+        }
+    }
+
+    masked.sort_by(|a, b| b.depth().total_cmp(&a.depth()));
+    for item in &masked {
+        match item {
+            MaskedItemT::Wall(seg) => draw_masked_seg(screen, &view, seg),
+            MaskedItemT::Sprite(spr) => draw_sprite(screen, spr, &clip_log),
+        }
     }
+    info.clip_log = clip_log;
+    info
 }
 
 pub fn sector_add_wall(sector: &mut SectorT, wall: WallT) {
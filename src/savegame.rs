@@ -0,0 +1,454 @@
+use std::{collections::HashSet, fs, path::PathBuf};
+use crate::{ai::{self, AiStateT, MonsterT}, entities::{self, TallyT}, game_state::{self, GameStateT}, items::RespawnT, lighting::{self, LightEffectT, LightFxT}, map, player::{self, PlayerT}, settings, sprites::SpriteThingT, typedefs::Vec2T, weapons::{self, ProjectileT}};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+// Subir SAVE_VERSION cada vez que cambie el formato: las partidas viejas se rechazan
+pub const SAVE_MAGIC: &str = "DOOMSAVE";
+pub const SAVE_VERSION: u32 = 10;
+pub const SAVES_DIR: &str = "saves";
+pub const SAVE_EXTENSION: &str = "sav";
+pub const QUICKSAVE_SLOT: &str = "quick";
+pub const SAVE_SLOTS: usize = 6; // Ranuras numeradas 1..6 ademas de la rapida
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Lo que cambia de un sector mientras se juega (la geometria sale del mapa)
+struct SectorStateT {
+    elevation: i32,
+    height: i32,
+    light: i32,
+    light_fx: LightFxT,
+    mover: (i32, i32, i32, i32), // Direccion, espera y limites (el tipo sale del mapa)
+    is_secret: bool,             // Secreto todavia sin encontrar
+}
+
+// Entidad guardada: su thing, en que punto de la animacion estaba y como estaba su IA
+struct ThingStateT {
+    thing: SpriteThingT,
+    anim: Option<(usize, i32)>,
+    monster: Option<MonsterStateT>,
+    projectile: Option<ProjectileT>,
+}
+
+struct MonsterStateT {
+    state: AiStateT,
+    step: usize,
+    tics: i32,
+    health: i32,
+    move_dir: Option<usize>,
+    move_count: i32,
+    reaction_time: i32,
+    is_alerted: bool,
+    just_attacked: bool,
+}
+
+// Partida leida de un archivo, todavia sin aplicar
+struct SaveT {
+    map_path: String,
+    tic: u64,
+    tic_accumulator: f64,
+    rng: u32,
+    player: PlayerT,
+    sectors: Vec<SectorStateT>,
+    things: Vec<ThingStateT>,
+    respawns: Vec<RespawnT>,
+    seen: HashSet<(usize, usize)>,
+    used: Vec<(usize, usize)>, // Paredes con specials de una sola vez ya disparados
+    stats: (i32, i32, i32, u64), // Muertes, items, secretos y tic de inicio (los totales salen del mapa)
+}
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+pub fn save_dir() -> Option<PathBuf> {
+    settings::config_dir().map(|dir| dir.join(SAVES_DIR))
+}
+
+// Solo 'quick' o un numero de ranura valido
+pub fn slot_path(slot: &str) -> Result<PathBuf, String> {
+    let is_numbered = slot.parse::<usize>().is_ok_and(|n| (1..=SAVE_SLOTS).contains(&n));
+    if slot != QUICKSAVE_SLOT && !is_numbered {
+        return Err(format!("ranura invalida '{}' (1..{} o {})", slot, SAVE_SLOTS, QUICKSAVE_SLOT));
+    }
+    let dir = save_dir().ok_or("no se encontro la carpeta de configuracion")?;
+    Ok(dir.join(format!("{}.{}", slot, SAVE_EXTENSION)))
+}
+
+fn effect_name(effect: LightEffectT) -> String {
+    match effect {
+        LightEffectT::None => "none".to_string(),
+        LightEffectT::Flicker => "flicker".to_string(),
+        LightEffectT::FireFlicker => "fire".to_string(),
+        LightEffectT::StrobeFast => "strobe_fast".to_string(),
+        LightEffectT::StrobeSlow => "strobe_slow".to_string(),
+        LightEffectT::Glow => "glow".to_string(),
+        LightEffectT::Transfer(source) => format!("transfer:{}", source),
+    }
+}
+
+fn parse_effect(name: &str) -> Result<LightEffectT, String> {
+    match name.strip_prefix("transfer:") {
+        Some(source) => source.parse().map(LightEffectT::Transfer).map_err(|_| format!("sector invalido '{}'", source)),
+        None => lighting::parse_effect(name).map(|(effect, _)| effect),
+    }
+}
+
+fn ai_state_name(state: AiStateT) -> &'static str {
+    match state {
+        AiStateT::Idle => "idle",
+        AiStateT::See => "see",
+        AiStateT::Chase => "chase",
+        AiStateT::Melee => "melee",
+        AiStateT::Missile => "missile",
+        AiStateT::Pain => "pain",
+        AiStateT::Death => "death",
+    }
+}
+
+fn parse_ai_state(name: &str) -> Result<AiStateT, String> {
+    let states = [AiStateT::Idle, AiStateT::See, AiStateT::Chase, AiStateT::Melee, AiStateT::Missile, AiStateT::Pain, AiStateT::Death];
+    states.into_iter().find(|&s| ai_state_name(s) == name).ok_or(format!("estado de IA desconocido '{}'", name))
+}
+
+fn flag(value: bool) -> u8 {
+    value as u8
+}
+
+// Una linea por dato, empezando por su clave
+pub fn serialize(state: &GameStateT, player: &PlayerT) -> String {
+    let mut out = format!("{} {}\n", SAVE_MAGIC, SAVE_VERSION);
+    out += &format!("map {}\n", state.map_path);
+    out += &format!("time {} {}\n", state.tic, state.tic_accumulator);
+    out += &format!("rng {}\n", state.rng.state);
+    let (tally, stats) = (&state.entities.tally, &state.stats);
+    out += &format!("stats {} {} {} {}\n", tally.kills, tally.items, stats.secrets, stats.start_tic);
+    out += &format!("player {} {} {} {} {} {} {} {}\n",
+        player.position.x, player.position.y, player.z, player.dir_angle,
+        player.damage_count, player.bonus_count, flag(player.is_noclip), flag(player.is_god));
+    let keys: String = player.keys.iter().map(|&k| if k { '1' } else { '0' }).collect();
+    out += &format!("health {} {} {} {} {} {} {}\n", player.health, player.armor, player.armor_type, keys,
+        player.vz, player.dead_tics, flag(player.is_flying));
+    let arsenal = &player.weapons;
+    let owned: String = arsenal.owned.iter().map(|&o| if o { '1' } else { '0' }).collect();
+    let pending = arsenal.pending.map_or("-".to_string(), |w| w.to_string());
+    out += &format!("weapons {} {} {} {} {}\n", arsenal.current, owned, pending, arsenal.cooldown, flag(arsenal.refire));
+    let ammo: Vec<String> = arsenal.ammo.iter().map(|a| a.to_string()).collect();
+    out += &format!("ammo {}\n", ammo.join(" "));
+    for sector in &state.sectors {
+        let fx = &sector.light_fx;
+        let min_override = fx.min_override.map_or("-".to_string(), |v| v.to_string());
+        let mover = &sector.mover;
+        out += &format!("sector {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n",
+            sector.elevation, sector.height, sector.light, effect_name(fx.effect), flag(fx.in_sync),
+            fx.min_light, fx.max_light, min_override, fx.count, fx.direction,
+            mover.direction, mover.count, mover.low, mover.high, flag(sector.is_secret));
+    }
+    for entity in state.entities.iter() {
+        let thing = &entity.thing;
+        let anim = entity.anim.as_ref().map_or("- -".to_string(), |a| format!("{} {}", a.step, a.count));
+        out += &format!("thing {} {} {} {} {} {} {}\n", thing.position.x, thing.position.y, thing.z, thing.angle, thing.sprite, thing.frame, anim);
+        // La IA va en la linea siguiente y se aplica al ultimo thing leido
+        if let Some(m) = &entity.monster {
+            let dir = m.move_dir.map_or("-".to_string(), |d| d.to_string());
+            out += &format!("monster {} {} {} {} {} {} {} {} {}\n", ai_state_name(m.state), m.step, m.tics, m.health,
+                dir, m.move_count, m.reaction_time, flag(m.is_alerted), flag(m.just_attacked));
+        }
+        // El dueño no se guarda (los ids cambian al cargar): solo si lo disparo el jugador
+        if let Some(p) = &entity.projectile {
+            let explode = p.explode_tics.map_or("-".to_string(), |t| t.to_string());
+            out += &format!("projectile {} {} {} {} {}\n", p.velocity.x, p.velocity.y, p.vz, flag(p.from_player), explode);
+        }
+    }
+    for respawn in &state.respawns {
+        let thing = &respawn.thing;
+        out += &format!("respawn {} {} {} {} {} {}\n", respawn.tic, thing.position.x, thing.position.y, thing.z, thing.angle, thing.sprite);
+    }
+    for (index, sector) in state.sectors.iter().enumerate() {
+        for (wall, _) in sector.walls.iter().enumerate().filter(|(_, w)| w.special.as_ref().is_some_and(|s| s.is_used)) {
+            out += &format!("used {} {}\n", index, wall);
+        }
+    }
+    let mut seen: Vec<&(usize, usize)> = state.automap.seen.iter().collect();
+    seen.sort();
+    for (sector, wall) in seen {
+        out += &format!("seen {} {}\n", sector, wall);
+    }
+    out
+}
+
+fn parse_save(source: &str) -> Result<SaveT, String> {
+    let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line.trim())).filter(|(_, line)| !line.is_empty());
+    // La version se revisa antes que nada: un formato distinto no se intenta leer
+    let header: Vec<&str> = lines.next().map(|(_, line)| line.split_whitespace().collect()).unwrap_or_default();
+    match header[..] {
+        [SAVE_MAGIC, version] if version == SAVE_VERSION.to_string() => {}
+        [SAVE_MAGIC, version] => return Err(format!("partida de la version {} (esta version usa la {})", version, SAVE_VERSION)),
+        _ => return Err("no es una partida guardada".to_string()),
+    }
+
+    let mut save = SaveT {
+        map_path: String::new(),
+        tic: 0,
+        tic_accumulator: 0.0,
+        rng: 1,
+        player: PlayerT::new(0.0, 0.0, 0.0, 0.0),
+        sectors: Vec::new(),
+        things: Vec::new(),
+        respawns: Vec::new(),
+        seen: HashSet::new(),
+        used: Vec::new(),
+        stats: (0, 0, 0, 0),
+    };
+    for (line_num, line) in lines {
+        let err = |msg: &str| format!("linea {}: {}", line_num, msg);
+        let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let num = |index: usize| -> Result<f64, String> {
+            fields.get(index).and_then(|v| v.parse::<f64>().ok()).ok_or(err(&format!("'{}': valor {} invalido", key, index + 1)))
+        };
+        let int = |index: usize| num(index).map(|v| v as i32);
+        match key {
+            "map" => save.map_path = rest.trim().to_string(),
+            "time" => {
+                save.tic = num(0)? as u64;
+                save.tic_accumulator = num(1)?;
+            }
+            "stats" => save.stats = (int(0)?.max(0), int(1)?.max(0), int(2)?.max(0), num(3)? as u64),
+            "rng" => save.rng = fields.first().and_then(|v| v.parse().ok()).ok_or(err("'rng': valor invalido"))?,
+            "player" => {
+                let player = &mut save.player;
+                player.position = Vec2T::new(num(0)?, num(1)?);
+                player.z = num(2)?;
+                player.dir_angle = num(3)?;
+                player.damage_count = int(4)?;
+                player.bonus_count = int(5)?;
+                player.is_noclip = int(6)? != 0;
+                player.is_god = int(7)? != 0;
+            }
+            "sector" => {
+                let effect = parse_effect(fields.get(3).copied().unwrap_or("")).map_err(|e| err(&e))?;
+                let min_override = match fields.get(7) {
+                    Some(&"-") => None,
+                    _ => Some(int(7)?),
+                };
+                let light_fx = LightFxT {
+                    effect,
+                    in_sync: int(4)? != 0,
+                    min_light: int(5)?,
+                    max_light: int(6)?,
+                    min_override,
+                    count: int(8)?,
+                    direction: int(9)?,
+                };
+                let mover = (int(10)?, int(11)?, int(12)?, int(13)?);
+                let is_secret = int(14)? != 0;
+                save.sectors.push(SectorStateT { elevation: int(0)?, height: int(1)?, light: int(2)?, light_fx, mover, is_secret });
+            }
+            "thing" => {
+                let [_, _, _, _, sprite, frame, step, _] = fields[..] else {
+                    return Err(err("'thing' necesita x, y, z, angulo, sprite, cuadro y animacion"));
+                };
+                let frame = frame.chars().next().unwrap_or('A');
+                let thing = SpriteThingT::new(num(0)?, num(1)?, num(2)?, num(3)?, sprite, frame);
+                let anim = if step == "-" { None } else { Some((int(6)? as usize, int(7)?)) };
+                save.things.push(ThingStateT { thing, anim, monster: None, projectile: None });
+            }
+            "monster" => {
+                let state = parse_ai_state(fields.first().copied().unwrap_or("")).map_err(|e| err(&e))?;
+                let move_dir = match fields.get(4) {
+                    Some(&"-") => None,
+                    _ => Some(int(4)? as usize),
+                };
+                let monster = MonsterStateT {
+                    state,
+                    step: int(1)? as usize,
+                    tics: int(2)?,
+                    health: int(3)?,
+                    move_dir,
+                    move_count: int(5)?,
+                    reaction_time: int(6)?,
+                    is_alerted: int(7)? != 0,
+                    just_attacked: int(8)? != 0,
+                };
+                let thing = save.things.last_mut().ok_or(err("'monster' sin un 'thing' antes"))?;
+                thing.monster = Some(monster);
+            }
+            "projectile" => {
+                let thing = save.things.last_mut().ok_or(err("'projectile' sin un 'thing' antes"))?;
+                let info = weapons::projectile_info(&thing.thing.sprite).ok_or(err(&format!("'{}' no es un proyectil", thing.thing.sprite)))?;
+                let explode_tics = match fields.get(4) {
+                    Some(&"-") => None,
+                    _ => Some(int(4)?),
+                };
+                let (velocity, vz, from_player) = (Vec2T::new(num(0)?, num(1)?), num(2)?, int(3)? != 0);
+                thing.projectile = Some(ProjectileT { info, velocity, vz, owner: None, from_player, explode_tics });
+            }
+            "health" => {
+                let player = &mut save.player;
+                let keys = fields.get(3).copied().unwrap_or("");
+                if keys.len() != player::NUM_KEYS || keys.chars().any(|c| c != '0' && c != '1') {
+                    return Err(err("'health': llaves invalidas"));
+                }
+                player.health = int(0)?.max(0);
+                player.armor = int(1)?.max(0);
+                player.armor_type = int(2)?.clamp(0, 2);
+                for (slot, c) in player.keys.iter_mut().zip(keys.chars()) {
+                    *slot = c == '1';
+                }
+                player.vz = num(4)?;
+                player.dead_tics = int(5)?;
+                player.is_flying = int(6)? != 0;
+            }
+            "weapons" => {
+                let arsenal = &mut save.player.weapons;
+                let owned = fields.get(1).copied().unwrap_or("");
+                if owned.len() != weapons::NUM_WEAPONS || owned.chars().any(|c| c != '0' && c != '1') {
+                    return Err(err("'weapons': armas invalidas"));
+                }
+                arsenal.current = (int(0)? as usize).min(weapons::NUM_WEAPONS - 1);
+                for (slot, c) in arsenal.owned.iter_mut().zip(owned.chars()) {
+                    *slot = c == '1';
+                }
+                arsenal.pending = match fields.get(2) {
+                    Some(&"-") => None,
+                    _ => Some((int(2)? as usize).min(weapons::NUM_WEAPONS - 1)),
+                };
+                arsenal.cooldown = int(3)?;
+                arsenal.refire = int(4)? != 0;
+            }
+            "ammo" => {
+                for index in 0..weapons::NUM_AMMO {
+                    save.player.weapons.ammo[index] = int(index)?.clamp(0, weapons::MAX_AMMO[index]);
+                }
+            }
+            "respawn" => {
+                let sprite = fields.get(5).ok_or(err("'respawn' necesita tic, x, y, z, angulo y sprite"))?;
+                let thing = SpriteThingT::new(num(1)?, num(2)?, num(3)?, num(4)?, sprite, 'A');
+                save.respawns.push(RespawnT { thing, tic: num(0)? as u64 });
+            }
+            "used" => {
+                save.used.push((int(0)? as usize, int(1)? as usize));
+            }
+            "seen" => {
+                save.seen.insert((int(0)? as usize, int(1)? as usize));
+            }
+            _ => return Err(err(&format!("clave desconocida '{}'", key))),
+        }
+    }
+    if save.map_path.is_empty() {
+        return Err("falta el mapa".to_string());
+    }
+    // El tiempo del nivel se calcula restando: el inicio no puede ser posterior al tic guardado
+    if save.stats.3 > save.tic {
+        return Err(format!("el nivel empieza en el tic {}, despues del tic guardado {}", save.stats.3, save.tic));
+    }
+    Ok(save)
+}
+
+pub fn save_game(state: &GameStateT, player: &PlayerT, slot: &str) -> Result<PathBuf, String> {
+    if state.map_path.is_empty() {
+        return Err("no hay un mapa cargado".to_string());
+    }
+    let path = slot_path(slot)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, serialize(state, player)).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+// Recarga el mapa guardado y le aplica el estado; si algo no cuadra la partida actual queda intacta
+pub fn load_game(state: &mut GameStateT, player: &mut PlayerT, slot: &str) -> Result<(), String> {
+    let path = slot_path(slot)?;
+    let source = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let save = parse_save(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let map = map::load_map(&save.map_path)?;
+    if map.sectors.len() != save.sectors.len() {
+        return Err(format!("{}: el mapa {} cambio desde que se guardo la partida", path.display(), save.map_path));
+    }
+    if let Some(&(sector, wall)) = save.seen.iter().find(|&&(s, w)| s >= map.sectors.len() || w >= map.sectors[s].walls.len()) {
+        return Err(format!("{}: pared vista {} {} fuera del mapa", path.display(), sector, wall));
+    }
+    let has_special = |&&(s, w): &&(usize, usize)| map.sectors.get(s).and_then(|s| s.walls.get(w)).is_some_and(|w| w.special.is_some());
+    if let Some(&(sector, wall)) = save.used.iter().find(|used| !has_special(used)) {
+        return Err(format!("{}: la pared {} {} no tiene special", path.display(), sector, wall));
+    }
+    game_state::load_level(state, player, &save.map_path)?;
+
+    for (sector, wall) in save.used {
+        if let Some(special) = &mut state.sectors[sector].walls[wall].special {
+            special.is_used = true;
+        }
+    }
+    for (sector, saved) in state.sectors.iter_mut().zip(save.sectors) {
+        sector.elevation = saved.elevation;
+        sector.height = saved.height;
+        sector.light = saved.light;
+        sector.light_fx = saved.light_fx;
+        (sector.mover.direction, sector.mover.count, sector.mover.low, sector.mover.high) = saved.mover;
+        // Solo se puede haber encontrado: un sector que no es secreto en el mapa no pasa a serlo
+        sector.is_secret &= saved.is_secret;
+    }
+    // Se vuelven a crear en el mismo orden, asi conservan sus ids (los totales son los del mapa)
+    let totals = state.entities.tally;
+    state.entities.reset(state.sectors.len());
+    for saved in save.things {
+        let Some(sector) = entities::find_sector(&state.sectors, saved.thing.position.x, saved.thing.position.y) else { continue; };
+        let id = state.entities.spawn(saved.thing, sector);
+        if let (Some(entity), Some((step, count))) = (state.entities.get_mut(id), saved.anim)
+            && let Some(anim) = &mut entity.anim {
+            anim.step = step % anim.frames.len();
+            anim.count = count;
+        }
+        if let (Some(entity), Some(saved)) = (state.entities.get_mut(id), saved.monster)
+            && let Some(monster) = &mut entity.monster {
+            restore_monster(monster, saved);
+            entity.thing.frame = monster.frame().frame;
+            entity.is_solid = monster.health > 0;
+        }
+        if let (Some(entity), Some(projectile)) = (state.entities.get_mut(id), saved.projectile) {
+            if projectile.explode_tics.is_some() {
+                entity.thing.frame = 'B';
+            }
+            entity.projectile = Some(projectile);
+        }
+    }
+    state.respawns = save.respawns;
+    state.automap.seen = save.seen;
+    state.tic = save.tic;
+    let (kills, items, secrets, start_tic) = save.stats;
+    state.entities.tally = TallyT { kills, items, ..totals };
+    (state.stats.secrets, state.stats.start_tic) = (secrets, start_tic);
+    state.tic_accumulator = save.tic_accumulator;
+    state.rng.state = save.rng.max(1);
+    *player = save.player;
+    Ok(())
+}
+
+fn restore_monster(monster: &mut MonsterT, saved: MonsterStateT) {
+    monster.state = saved.state;
+    // Un estado vacio (melee de un monstruo que no lo tiene) se trata como persecucion
+    if monster.info.frames(saved.state).is_empty() {
+        monster.state = AiStateT::Chase;
+    }
+    monster.step = saved.step.min(monster.info.frames(monster.state).len() - 1);
+    monster.tics = saved.tics;
+    monster.health = saved.health;
+    monster.move_dir = saved.move_dir.filter(|&d| d < ai::NUM_DIRS);
+    monster.move_count = saved.move_count;
+    monster.reaction_time = saved.reaction_time;
+    monster.is_alerted = saved.is_alerted;
+    monster.just_attacked = saved.just_attacked;
+}
+
+// Ranuras con partida y su mapa, para listarlas en la consola
+pub fn list_slots() -> Vec<(String, String)> {
+    let slots = (1..=SAVE_SLOTS).map(|n| n.to_string()).chain(std::iter::once(QUICKSAVE_SLOT.to_string()));
+    slots.filter_map(|slot| {
+        let source = fs::read_to_string(slot_path(&slot).ok()?).ok()?;
+        let description = match parse_save(&source) {
+            Ok(save) => format!("{} (tic {})", save.map_path, save.tic),
+            Err(e) => e,
+        };
+        Some((slot, description))
+    }).collect()
+}
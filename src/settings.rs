@@ -0,0 +1,138 @@
+use std::{fs, path::PathBuf};
+use sdl2::keyboard::Scancode;
+use crate::{keyboard::KeymapT, renderer};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const APP_DIR: &str = "doom-rust";
+pub const SETTINGS_FILE: &str = "settings.cfg";
+
+// Resoluciones que ofrece el menu de opciones
+pub const RESOLUTIONS: [(u32, u32); 6] = [(640, 480), (800, 600), (1024, 768), (1280, 720), (1280, 960), (1920, 1080)];
+pub const FPS_LIMITS: [f64; 6] = [35.0, 60.0, 75.0, 120.0, 144.0, 240.0];
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Todo lo que se guarda entre partidas
+#[derive(Clone)]
+pub struct SettingsT {
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub fps_capped: bool,
+    pub target_fps: f64,
+    pub fov: f64,               // En grados
+    pub mouse_sensitivity: f64,
+    pub volume: u32,            // 0..100
+    pub render_scale: f64,
+    pub keymap: KeymapT,
+}
+    impl SettingsT {
+        pub fn new() -> Self {
+            SettingsT {
+                width: 1024,
+                height: 768,
+                fullscreen: false,
+                fps_capped: true,
+                target_fps: 120.0,
+                fov: renderer::DEFAULT_FOV.to_degrees(),
+                mouse_sensitivity: 5.0,
+                volume: 80,
+                render_scale: 1.0,
+                keymap: KeymapT::new(),
+            }
+        }
+
+        // Formato 'clave = valor', una por linea ('#' para comentarios)
+        pub fn parse(source: &str) -> Result<Self, String> {
+            let mut settings = SettingsT::new();
+            for (line_num, raw_line) in source.lines().enumerate() {
+                let line = raw_line.split('#').next().unwrap_or("").trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let Some((key, value)) = line.split_once('=') else {
+                    return Err(format!("linea {}: se esperaba 'clave = valor'", line_num + 1));
+                };
+                settings.set(key.trim(), value.trim()).map_err(|e| format!("linea {}: {}", line_num + 1, e))?;
+            }
+            Ok(settings)
+        }
+
+        fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+            let number = |value: &str| value.parse::<f64>().map_err(|_| format!("{}: numero invalido '{}'", key, value));
+            let flag = |value: &str| match value {
+                "1" | "true" => Ok(true),
+                "0" | "false" => Ok(false),
+                _ => Err(format!("{}: valor booleano invalido '{}'", key, value)),
+            };
+            match key {
+                "width" => self.width = (number(value)? as u32).max(160),
+                "height" => self.height = (number(value)? as u32).max(120),
+                "fullscreen" => self.fullscreen = flag(value)?,
+                "fps_capped" => self.fps_capped = flag(value)?,
+                "target_fps" => self.target_fps = number(value)?.clamp(10.0, 1000.0),
+                "fov" => self.fov = number(value)?.clamp(30.0, 150.0),
+                "mouse_sensitivity" => self.mouse_sensitivity = number(value)?.clamp(0.0, 20.0),
+                "volume" => self.volume = (number(value)? as u32).min(100),
+                "render_scale" => self.render_scale = number(value)?.clamp(0.25, 1.0),
+                _ => {
+                    let action = key.strip_prefix("bind_").ok_or(format!("clave desconocida '{}'", key))?;
+                    let scancode = Scancode::from_name(value).ok_or(format!("{}: tecla desconocida '{}'", key, value))?;
+                    let slot = self.keymap.binding_mut(action).ok_or(format!("accion desconocida '{}'", action))?;
+                    *slot = scancode;
+                }
+            }
+            Ok(())
+        }
+
+        pub fn serialize(&self) -> String {
+            let mut out = String::from("# Configuracion del motor (se reescribe al salir del menu de opciones)\n");
+            out += &format!("width = {}\nheight = {}\nfullscreen = {}\n", self.width, self.height, self.fullscreen);
+            out += &format!("fps_capped = {}\ntarget_fps = {}\n", self.fps_capped, self.target_fps);
+            out += &format!("fov = {}\nmouse_sensitivity = {}\nvolume = {}\n", self.fov, self.mouse_sensitivity, self.volume);
+            out += &format!("render_scale = {}\n", self.render_scale);
+            for (action, scancode) in self.keymap.bindings() {
+                out += &format!("bind_{} = {}\n", action, scancode.name());
+            }
+            out
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+// Carpeta de configuracion del usuario segun el sistema
+pub fn config_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    };
+    base.map(|dir| dir.join(APP_DIR))
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(SETTINGS_FILE))
+}
+
+// Lee la configuracion guardada; si no hay (o esta rota) usa los valores por defecto
+pub fn load() -> SettingsT {
+    let Some(path) = settings_path() else {
+        return SettingsT::new();
+    };
+    let Ok(source) = fs::read_to_string(&path) else {
+        return SettingsT::new();
+    };
+    SettingsT::parse(&source).unwrap_or_else(|e| {
+        eprintln!("{}: {} (se usan los valores por defecto)", path.display(), e);
+        SettingsT::new()
+    })
+}
+
+pub fn save(settings: &SettingsT) -> Result<(), String> {
+    let dir = config_dir().ok_or("no se encontro la carpeta de configuracion")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let path = dir.join(SETTINGS_FILE);
+    fs::write(&path, settings.serialize()).map_err(|e| format!("{}: {}", path.display(), e))
+}
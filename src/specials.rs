@@ -0,0 +1,194 @@
+use crate::{collision, game_state::GameStateT, hud, intermission, lighting, movers::{self, TriggerT}, player::PlayerT, renderer::{SectorT, WallT}, typedefs::Vec2T};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const USE_RANGE: f64 = 64.0; // Alcance de la tecla de usar (como USERANGE)
+pub const SECRET_MESSAGE: &str = "A SECRET IS REVEALED!";
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Lo que hace una pared especial sobre los sectores a los que apunta
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ActionT {
+    Move,     // Pone en marcha la puerta, ascensor o trituradora del sector
+    Stop,     // Lo frena donde esta
+    LightOn,  // Luz al nivel del vecino mas claro
+    LightOff, // Luz al nivel del vecino mas oscuro
+    Exit,     // Termina el nivel (no apunta a ningun sector)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineTriggerT {
+    Use,   // Con la tecla de usar (interruptores)
+    Walk,  // Al cruzarla caminando
+    Shoot, // Al pegarle un tiro
+}
+
+#[derive(Clone)]
+pub struct SpecialT {
+    pub action: ActionT,
+    pub trigger: LineTriggerT,
+    pub tag: i32,           // Sectores con este tag (0 = el del otro lado del portal)
+    pub is_repeatable: bool,
+    pub is_used: bool,      // Los de una sola vez no vuelven a andar
+}
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+
+// Traducen los nombres usados en el archivo de mapa
+pub fn parse_action(name: &str) -> Result<ActionT, String> {
+    match name {
+        "move" => Ok(ActionT::Move),
+        "stop" => Ok(ActionT::Stop),
+        "light_on" => Ok(ActionT::LightOn),
+        "light_off" => Ok(ActionT::LightOff),
+        "exit" => Ok(ActionT::Exit),
+        _ => Err(format!("special desconocido '{}'", name)),
+    }
+}
+
+pub fn parse_trigger(name: &str) -> Result<LineTriggerT, String> {
+    match name {
+        "use" => Ok(LineTriggerT::Use),
+        "walk" => Ok(LineTriggerT::Walk),
+        "shoot" => Ok(LineTriggerT::Shoot),
+        _ => Err(format!("disparador desconocido '{}'", name)),
+    }
+}
+
+// Special de la pared que todavia se puede disparar de esa forma
+fn armed(sectors: &[SectorT], sector: usize, wall: usize, trigger: LineTriggerT) -> Option<SpecialT> {
+    sectors[sector].walls[wall].special.clone().filter(|s| s.trigger == trigger && !s.is_used)
+}
+
+// Sectores a los que apunta el special: los del tag o, sin tag, el del otro lado del portal
+fn targets(sectors: &[SectorT], sector: usize, wall: usize, tag: i32) -> Vec<usize> {
+    if tag == 0 {
+        return sectors[sector].walls[wall].neighbor.into_iter().collect();
+    }
+    (0..sectors.len()).filter(|&s| sectors[s].tag == tag).collect()
+}
+
+// Ejecuta el special de una pared sobre sus sectores
+fn run_special(state: &mut GameStateT, sector: usize, wall: usize, special: &SpecialT) {
+    if special.action == ActionT::Exit {
+        intermission::finish_level(state);
+        return;
+    }
+    for target in targets(&state.sectors, sector, wall, special.tag) {
+        match special.action {
+            ActionT::Move => { movers::start(&mut state.sectors, target); }
+            ActionT::Stop => { movers::stop(&mut state.sectors, target); }
+            ActionT::LightOn => {
+                let level = lighting::highest_neighbor_light(&state.sectors, target);
+                lighting::set_light(&mut state.sectors[target], level);
+            }
+            ActionT::LightOff => {
+                let level = lighting::lowest_neighbor_light(&state.sectors, target);
+                lighting::set_light(&mut state.sectors[target], level);
+            }
+            ActionT::Exit => {}
+        }
+    }
+    // Como en Doom el de una sola vez se gasta aunque no haya movido nada
+    if !special.is_repeatable && let Some(special) = &mut state.sectors[sector].walls[wall].special {
+        special.is_used = true;
+    }
+}
+
+// Tecla de usar: la primera pared que cruza un rayo corto desde el jugador. Un interruptor
+// dispara su special; un portal activa la puerta o el ascensor de atras; si no, sigue de largo
+pub fn use_lines(state: &mut GameStateT, player: &PlayerT) {
+    let from = player.position.clone();
+    let to = Vec2T::new(from.x + player.dir_angle.cos() * USE_RANGE, from.y + player.dir_angle.sin() * USE_RANGE);
+    let Some(mut sector) = state.sector_at(from.x, from.y) else { return; };
+    let mut last_t = 0.0;
+    for _ in 0..=state.sectors.len() {
+        let crossing = state.sectors[sector].walls.iter().enumerate()
+            .filter_map(|(index, wall)| collision::segment_crossing(&from, &to, &wall.a, &wall.b).map(|t| (t, index)))
+            .filter(|(t, _)| *t > last_t + 1e-9)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((t, wall)) = crossing else { return; };
+        if let Some(special) = armed(&state.sectors, sector, wall, LineTriggerT::Use) {
+            run_special(state, sector, wall, &special);
+            return;
+        }
+        let Some(next) = state.sectors[sector].walls[wall].neighbor else { return; };
+        if movers::activate(&mut state.sectors, next, TriggerT::Use) {
+            return;
+        }
+        let (floor, ceil) = collision::opening(&state.sectors[sector], &state.sectors[next]);
+        if ceil <= floor {
+            return;
+        }
+        sector = next;
+        last_t = t;
+    }
+}
+
+// Sector secreto: cuenta una sola vez, asi que se apaga al encontrarlo
+fn find_secret(state: &mut GameStateT, sector: usize) {
+    if !state.sectors[sector].is_secret {
+        return;
+    }
+    state.sectors[sector].is_secret = false;
+    state.stats.secrets += 1;
+    hud::show_message(&mut state.hud, state.tic, SECRET_MESSAGE);
+}
+
+// La pared del otro lado de un portal: el mismo borde en el vecino, apuntando de vuelta
+fn mirror_wall(sectors: &[SectorT], sector: usize, wall: usize) -> Option<(usize, usize)> {
+    let w = &sectors[sector].walls[wall];
+    let next = w.neighbor?;
+    let same_edge = |o: &WallT| (o.a == w.a && o.b == w.b) || (o.a == w.b && o.b == w.a);
+    sectors[next].walls.iter().position(|o| o.neighbor == Some(sector) && same_edge(o)).map(|index| (next, index))
+}
+
+// Portal cruzado caminando: el special puede estar en cualquiera de los dos lados, pero es una
+// sola linea, asi que corre una vez y el de una sola vez queda gastado en los dos
+fn cross_line(state: &mut GameStateT, sector: usize, wall: usize) {
+    let sides = [Some((sector, wall)), mirror_wall(&state.sectors, sector, wall)];
+    let Some((s, w, special)) = sides.into_iter().flatten()
+        .find_map(|(s, w)| armed(&state.sectors, s, w, LineTriggerT::Walk).map(|special| (s, w, special))) else { return; };
+    run_special(state, s, w, &special);
+    if special.is_repeatable {
+        return;
+    }
+    for (s, w) in sides.into_iter().flatten() {
+        if let Some(other) = &mut state.sectors[s].walls[w].special && other.trigger == LineTriggerT::Walk {
+            other.is_used = true;
+        }
+    }
+}
+
+// Al moverse el jugador: los portales que cruzo caminando y los sectores por los que paso
+// (el secreto se revisa aunque no haya cambiado de sector, por si empieza en uno)
+pub fn player_moved(state: &mut GameStateT, player: &PlayerT, from: &Vec2T) {
+    let to = &player.position;
+    let (Some(mut sector), Some(new)) = (state.sector_at(from.x, from.y), state.sector_at(to.x, to.y)) else { return; };
+    find_secret(state, new);
+    if sector == new {
+        return;
+    }
+    // Se sigue el recorrido portal por portal: en un frame rapido puede pasar por un sector intermedio
+    let mut last_t = -1.0; // El primer cruce puede estar justo en from
+    for _ in 0..state.sectors.len() {
+        let crossing = state.sectors[sector].walls.iter().enumerate()
+            .filter_map(|(index, wall)| collision::segment_crossing(from, to, &wall.a, &wall.b).map(|t| (t, index)))
+            .filter(|(t, _)| *t > last_t + 1e-9)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((t, wall)) = crossing else { break; };
+        let Some(next) = state.sectors[sector].walls[wall].neighbor else { break; };
+        cross_line(state, sector, wall);
+        find_secret(state, next);
+        sector = next;
+        last_t = t;
+    }
+    movers::activate(&mut state.sectors, new, TriggerT::Walk);
+}
+
+// Un tiro que freno en una pared (sector, indice)
+pub fn shoot_line(state: &mut GameStateT, sector: usize, wall: usize) {
+    if let Some(special) = armed(&state.sectors, sector, wall, LineTriggerT::Shoot) {
+        run_special(state, sector, wall, &special);
+    }
+}
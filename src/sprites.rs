@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_4, FRAC_PI_8, PI, TAU};
+use crate::{hud, items::{self, EffectT}, palette, textures::{TextureT, TexturesT}, typedefs, wad::WadT, weapons};
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Objeto del mundo que se dibuja como sprite
+#[derive(Clone)]
+pub struct SpriteThingT {
+    pub position: typedefs::Vec2T,
+    pub z: f64,           // Altura de los pies
+    pub angle: f64,       // Hacia donde mira (para elegir la rotacion)
+    pub sprite: String,   // Nombre de 4 letras (TROO, BAR1...)
+    pub frame: char,      // Cuadro de animacion (A, B, ...)
+}
+    impl SpriteThingT {
+        pub fn new(x: f64, y: f64, z: f64, angle: f64, sprite: &str, frame: char) -> Self {
+            SpriteThingT {
+                position: typedefs::Vec2T::new(x, y),
+                z,
+                angle,
+                sprite: sprite.to_ascii_uppercase(),
+                frame: frame.to_ascii_uppercase(),
+            }
+        }
+    }
+
+// Lump a usar para una rotacion (y si hay que espejarlo)
+#[derive(Clone)]
+pub struct SpriteLumpT {
+    pub texture: String,
+    pub flip: bool,
+}
+
+// Un cuadro: una sola imagen para todos los angulos o 8 rotaciones
+#[derive(Clone)]
+pub struct SpriteFrameT {
+    pub rotations: [Option<SpriteLumpT>; 8],
+    pub rotates: bool,
+}
+    impl SpriteFrameT {
+        fn new() -> Self {
+            SpriteFrameT { rotations: Default::default(), rotates: false }
+        }
+    }
+
+pub struct SpritesT {
+    pub frames: HashMap<String, HashMap<char, SpriteFrameT>>,
+}
+    impl SpritesT {
+        pub fn new() -> Self {
+            SpritesT { frames: HashMap::new() }
+        }
+
+        // Registra un lump con nombre estilo Doom: SSSSFR[FR] (sprite, cuadro, rotacion)
+        pub fn add_lump(&mut self, lump_name: &str) {
+            let bytes: Vec<char> = lump_name.chars().collect();
+            if bytes.len() < 6 {
+                return;
+            }
+            let sprite: String = bytes[0..4].iter().collect();
+            self.add_rotation(&sprite, bytes[4], bytes[5], lump_name, false);
+            if bytes.len() >= 8 {
+                self.add_rotation(&sprite, bytes[6], bytes[7], lump_name, true);
+            }
+        }
+
+        fn add_rotation(&mut self, sprite: &str, frame: char, rotation: char, lump_name: &str, flip: bool) {
+            let Some(rotation) = rotation.to_digit(10) else { return; };
+            let entry = self.frames.entry(sprite.to_string()).or_default().entry(frame).or_insert_with(SpriteFrameT::new);
+            let lump = SpriteLumpT { texture: lump_name.to_string(), flip };
+            if rotation == 0 {
+                entry.rotates = false;
+                for slot in entry.rotations.iter_mut() {
+                    *slot = Some(lump.clone());
+                }
+            } else if rotation <= 8 {
+                entry.rotates = true;
+                entry.rotations[rotation as usize - 1] = Some(lump);
+            }
+        }
+
+        // Imagen sin rotaciones (armas en primera persona); si falta el cuadro se usa el A
+        pub fn frame_lump(&self, sprite: &str, frame: char) -> Option<&SpriteLumpT> {
+            let frames = self.frames.get(sprite)?;
+            frames.get(&frame).or_else(|| frames.get(&'A'))?.rotations[0].as_ref()
+        }
+
+        // Elige la imagen segun el angulo desde el que se mira al objeto
+        // (si falta el cuadro de la animacion, como en los sprites incluidos, se usa el A)
+        pub fn select(&self, thing: &SpriteThingT, view_x: f64, view_y: f64) -> Option<&SpriteLumpT> {
+            let frames = self.frames.get(&thing.sprite)?;
+            let frame = frames.get(&thing.frame).or_else(|| frames.get(&'A'))?;
+            if !frame.rotates {
+                return frame.rotations[0].as_ref();
+            }
+            let to_thing = (thing.position.y - view_y).atan2(thing.position.x - view_x);
+            let rotation = ((to_thing - thing.angle + PI + FRAC_PI_8).rem_euclid(TAU) / FRAC_PI_4) as usize % 8;
+            frame.rotations[rotation].as_ref()
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+
+// Lee todos los sprites entre S_START y S_END del WAD
+fn load_wad_sprites(wad: &WadT, sprites: &mut SpritesT, textures: &mut TexturesT) -> Result<(), String> {
+    let start = wad.find_lump("S_START").or(wad.find_lump("SS_START")).ok_or(format!("{}: no tiene S_START", wad.path))?;
+    let end = wad.find_lump("S_END").or(wad.find_lump("SS_END")).ok_or(format!("{}: no tiene S_END", wad.path))?;
+    let base = palette::base_palette(wad)?;
+
+    for index in start + 1..end {
+        let lump = &wad.lumps[index];
+        if lump.size == 0 {
+            continue;
+        }
+        match TextureT::from_patch(&lump.name, wad.lump_bytes(index), &base) {
+            Ok(texture) => {
+                textures.insert(texture);
+                sprites.add_lump(&lump.name);
+            }
+            Err(e) => eprintln!("Sprite ignorado: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn builtin_patch(name: &str, width: usize, height: usize, paint: impl Fn(usize, usize) -> Option<u32>) -> TextureT {
+    let mut texture = TextureT::new(name, width, height);
+    texture.left_offset = width as i32 / 2;
+    texture.top_offset = height as i32;
+    for x in 0..width {
+        for y in 0..height {
+            if let Some(color) = paint(x, y) {
+                texture.set(x, y, color);
+            }
+        }
+    }
+    texture
+}
+
+// Armas en primera persona: los offsets son como los de Doom, relativos a una pantalla de 320x200
+// con el arma en reposo (el arma queda centrada y apoyada en el borde de abajo)
+fn builtin_weapon(index: usize) -> (TextureT, Option<TextureT>) {
+    let info = &weapons::WEAPONS[index];
+    let (width, height) = (48 + index * 8, 64 + index * 4);
+    let barrel = 6 + index as i32 * 2;
+    let mut gun = builtin_patch(&format!("{}A0", info.sprite), width, height, |x, y| {
+        let dx = (x as i32 - width as i32 / 2).abs();
+        if matches!(info.fire, weapons::FireT::Melee(_)) {
+            // Puño: una mano redondeada color piel
+            let dy = y as i32 - height as i32 / 3;
+            return (dx * dx + dy * dy <= (width as i32 / 2).pow(2) || (y as i32 > height as i32 / 3 && dx < width as i32 / 3)).then_some(0xC08060);
+        }
+        // Caño arriba y cuerpo que se ensancha hacia abajo
+        let body = y as i32 > height as i32 / 3 && dx <= barrel + (y as i32 - height as i32 / 3) / 2;
+        if dx > barrel && !body {
+            return None;
+        }
+        Some(if dx < barrel / 3 { 0xA0A0A0 } else if body { 0x7A5838 } else { 0x686868 })
+    });
+    gun.left_offset = width as i32 / 2 - 159;
+    gun.top_offset = height as i32 - 168;
+
+    let flash = info.flash.map(|name| {
+        let size = 24 + index * 4;
+        let r = size as f64 / 2.0;
+        let mut flash = builtin_patch(&format!("{}A0", name), size, size, |x, y| {
+            let d = ((x as f64 + 0.5 - r).powi(2) + (y as f64 + 0.5 - r).powi(2)).sqrt() / r;
+            (d <= 1.0).then_some(if d < 0.4 { 0xFFFFC0 } else if d < 0.75 { 0xFFC040 } else { 0xF07000 })
+        });
+        // Sobre la punta del caño
+        flash.left_offset = size as i32 / 2 - 159;
+        flash.top_offset = size as i32 + height as i32 - 168 - size as i32 / 3;
+        flash
+    });
+    (gun, flash)
+}
+
+// Items: una forma simple con el color de lo que dan (cruz roja, chaleco, caja, arma o tarjeta)
+fn builtin_item(info: &items::ItemInfoT) -> TextureT {
+    let name = format!("{}A0", info.sprite);
+    match info.effect {
+        EffectT::Health(..) => builtin_patch(&name, 16, 14, |x, y| {
+            let cross = (6..10).contains(&x) && (2..12).contains(&y) || (3..13).contains(&x) && (5..9).contains(&y);
+            Some(if cross { 0xE00000 } else { 0xE0E0E0 })
+        }),
+        EffectT::Armor(..) | EffectT::ArmorBonus(_) => {
+            let color = if matches!(info.effect, EffectT::Armor(_, 2)) { 0x3050E0 } else { 0x40B040 };
+            builtin_patch(&name, 20, 16, move |x, y| {
+                let neck = (7..13).contains(&x) && y < 4;
+                (!neck).then_some(color)
+            })
+        }
+        EffectT::Ammo(..) => builtin_patch(&name, 16, 10, |_, y| Some(if y < 3 { 0xE0C040 } else { 0x806020 })),
+        EffectT::Weapon(..) => builtin_patch(&name, 32, 10, |x, y| (y >= 4 || x > 20).then_some(if y < 4 { 0x606060 } else { 0x404040 })),
+        EffectT::Key(key) => builtin_patch(&name, 10, 14, move |x, y| {
+            let hole = (4..6).contains(&x) && (2..4).contains(&y);
+            (!hole).then_some(hud::KEY_COLORS[key])
+        }),
+    }
+}
+
+// Sprites generados para cuando no hay WAD: barril, lampara, proyectiles, armas, items y un soldado con 8 rotaciones
+fn load_builtin_sprites(sprites: &mut SpritesT, textures: &mut TexturesT) {
+    let barrel = builtin_patch("BAR1A0", 24, 32, |x, y| {
+        let shade = 120 + (12 - (x as i32 - 12).abs()) as u32 * 8;
+        let band = y % 10 < 2;
+        Some(if band { palette::rgb(shade / 2, shade / 2, shade / 2) } else { palette::rgb(40, shade, 40) })
+    });
+    let lamp = builtin_patch("COLUA0", 12, 48, |x, y| {
+        let dx = x as i32 - 6;
+        if y < 10 {
+            (dx * dx + (y as i32 - 5).pow(2) <= 25).then_some(0xFFF0A0)
+        } else {
+            (dx.abs() <= 2 || y > 44).then_some(0x707070)
+        }
+    });
+    // Proyectiles: bolas de colores con el centro mas claro
+    let ball = |name: &str, size: usize, core: u32, edge: u32| builtin_patch(name, size, size, move |x, y| {
+        let r = size as f64 / 2.0;
+        let d = ((x as f64 + 0.5 - r).powi(2) + (y as f64 + 0.5 - r).powi(2)).sqrt() / r;
+        (d <= 1.0).then_some(if d < 0.5 { core } else { edge })
+    });
+    let rocket = ball("MISLA0", 12, 0xFFF0C0, 0xFC7000);
+    let plasma = ball("PLSSA0", 12, 0xE0F0FF, 0x3060FC);
+    let imp_ball = ball("BAL1A0", 10, 0xFFE080, 0xE02000);
+    for texture in [barrel, lamp, rocket, plasma, imp_ball] {
+        sprites.add_lump(&texture.name);
+        textures.insert(texture);
+    }
+    for info in &items::ITEMS {
+        let texture = builtin_item(info);
+        sprites.add_lump(&texture.name);
+        textures.insert(texture);
+    }
+    for index in 0..weapons::NUM_WEAPONS {
+        let (gun, flash) = builtin_weapon(index);
+        for texture in std::iter::once(gun).chain(flash) {
+            sprites.add_lump(&texture.name);
+            textures.insert(texture);
+        }
+    }
+
+    // Soldado: la franja clara marca el frente y se corre segun la rotacion
+    for rotation in 1..=8u32 {
+        let name = format!("POSSA{}", rotation);
+        let facing = (rotation - 1) as f64 * FRAC_PI_4;
+        let soldier = builtin_patch(&name, 32, 56, |x, y| {
+            let dx = (x as f64 - 16.0) / 16.0;
+            let head = y < 14 && dx.abs() < 0.35;
+            let body = (14..40).contains(&y) && dx.abs() < 0.7;
+            let legs = y >= 40 && dx.abs() < 0.55 && dx.abs() >= 0.1;
+            if !(head || body || legs) {
+                return None;
+            }
+            // Posicion del frente sobre el cuerpo: centro en rotacion 1, oculto en 5
+            let front = dx.asin() + facing;
+            let lit = front.cos() > 0.5;
+            Some(match (head, lit) {
+                (true, true) => 0xE0B090,
+                (true, false) => 0x704830,
+                (false, true) => 0x50A050,
+                (false, false) => 0x285028,
+            })
+        });
+        sprites.add_lump(&name);
+        textures.insert(soldier);
+    }
+}
+
+pub fn load_sprites(wad: Option<&WadT>, textures: &mut TexturesT) -> SpritesT {
+    let mut sprites = SpritesT::new();
+    load_builtin_sprites(&mut sprites, textures);
+    if let Some(wad) = wad && let Err(e) = load_wad_sprites(wad, &mut sprites, textures) {
+        eprintln!("Error al cargar sprites del WAD: {}", e);
+    }
+    sprites
+}
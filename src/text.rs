@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use sdl2::{pixels::PixelFormatEnum, surface::Surface};
+use crate::{palette, renderer::Screen, textures::{TextureT, TRANSPARENT}, wad::WadT};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const GLYPH_W: i32 = 3;
+pub const GLYPH_H: i32 = 5;
+pub const GLYPH_SPACING: i32 = 1;
+pub const LINE_SPACING: i32 = 2;
+pub const SHADOW_CLR: u32 = 0x000000;
+
+// Fuente del HUD de Doom: STCFN033 ('!') .. STCFN121 ('y')
+pub const WAD_FONT_PREFIX: &str = "STCFN";
+pub const WAD_FONT_FIRST: u8 = b'!';
+pub const WAD_FONT_LAST: u8 = b'y';
+
+// Atlas opcional que reemplaza a las otras fuentes: 16x6 celdas desde el espacio (ASCII 32..127)
+pub const ATLAS_FONT_PATH: &str = "assets/font.bmp";
+pub const ATLAS_COLS: usize = 16;
+pub const ATLAS_ROWS: usize = 6;
+
+// Fuente 3x5: cada digito octal es una fila (de arriba hacia abajo), el bit alto es la columna izquierda
+const FONT_3X5: [(char, u16); 60] = [
+    (' ', 0o00000), ('0', 0o75557), ('1', 0o26227), ('2', 0o71747), ('3', 0o71317), ('4', 0o55711),
+    ('5', 0o74717), ('6', 0o74757), ('7', 0o71222), ('8', 0o75757), ('9', 0o75717), ('A', 0o25755),
+    ('B', 0o65656), ('C', 0o34443), ('D', 0o65556), ('E', 0o74647), ('F', 0o74644), ('G', 0o34553),
+    ('H', 0o55755), ('I', 0o72227), ('J', 0o11152), ('K', 0o55655), ('L', 0o44447), ('M', 0o57755),
+    ('N', 0o65555), ('O', 0o25552), ('P', 0o65644), ('Q', 0o25563), ('R', 0o65655), ('S', 0o34216),
+    ('T', 0o72222), ('U', 0o55557), ('V', 0o55552), ('W', 0o55775), ('X', 0o55255), ('Y', 0o55222),
+    ('Z', 0o71247), ('.', 0o00002), (',', 0o00024), (':', 0o02020), (';', 0o02024), ('-', 0o00700),
+    ('+', 0o02720), ('=', 0o07070), ('/', 0o11244), ('(', 0o24442), (')', 0o21112), ('[', 0o64446),
+    (']', 0o31113), ('<', 0o12421), ('>', 0o42124), ('%', 0o51245), ('_', 0o00007), ('!', 0o22202),
+    ('?', 0o61202), ('\'', 0o22000), ('"', 0o55000), ('*', 0o05250), ('#', 0o57575), ('|', 0o22222),
+];
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy, PartialEq)]
+pub enum AlignT {
+    Left,
+    Center,
+    Right,
+}
+
+// Como se dibuja un texto: color None respeta los colores de la fuente (STCFN es roja)
+#[derive(Clone, Copy)]
+pub struct TextStyleT {
+    pub color: Option<u32>,
+    pub scale: i32,
+    pub align: AlignT,
+}
+    impl TextStyleT {
+        pub fn new(color: u32, scale: i32) -> Self {
+            TextStyleT { color: Some(color), scale, align: AlignT::Left }
+        }
+
+        pub fn aligned(mut self, align: AlignT) -> Self {
+            self.align = align;
+            self
+        }
+    }
+
+pub struct FontT {
+    pub name: String,
+    pub glyphs: HashMap<char, TextureT>,
+    pub height: i32,      // Alto de una linea (sin el interlineado)
+    pub space_width: i32, // Ancho de los caracteres que no estan en la fuente
+    pub spacing: i32,     // Separacion entre glifos
+    pub upper_only: bool, // Las minusculas se dibujan con las mayusculas
+}
+    impl FontT {
+        // Fuente 3x5 incluida en el motor (siempre disponible)
+        pub fn builtin() -> Self {
+            let mut glyphs = HashMap::new();
+            for (c, bits) in FONT_3X5 {
+                let mut glyph = TextureT::new(&c.to_string(), GLYPH_W as usize, GLYPH_H as usize);
+                for row in 0..GLYPH_H {
+                    for col in 0..GLYPH_W {
+                        let bit = (GLYPH_H - 1 - row) * GLYPH_W + (GLYPH_W - 1 - col);
+                        if bits & (1 << bit) != 0 {
+                            glyph.set(col as usize, row as usize, 0xFFFFFF);
+                        }
+                    }
+                }
+                glyphs.insert(c, glyph);
+            }
+            FontT { name: "builtin".to_string(), glyphs, height: GLYPH_H, space_width: GLYPH_W, spacing: GLYPH_SPACING, upper_only: true }
+        }
+
+        // Fuente de un WAD a partir de lumps <prefijo><codigo ascii de 3 digitos> (STCFN065 = 'A')
+        pub fn from_wad(wad: &WadT, prefix: &str, first: u8, last: u8) -> Result<Self, String> {
+            let base = palette::base_palette(wad)?;
+            let mut glyphs = HashMap::new();
+            for code in first..=last {
+                let lump = format!("{}{:03}", prefix, code);
+                if let Some(data) = wad.lump(&lump) {
+                    glyphs.insert(code as char, TextureT::from_patch(&lump, data, &base)?);
+                }
+            }
+            if glyphs.is_empty() {
+                return Err(format!("{}: no tiene lumps {}###", wad.path, prefix));
+            }
+            let height = glyphs.values().map(|g| g.height as i32).max().unwrap_or(0);
+            let upper_only = !glyphs.keys().any(|c| c.is_ascii_lowercase());
+            Ok(FontT { name: prefix.to_string(), glyphs, height, space_width: 4, spacing: 0, upper_only })
+        }
+
+        // Atlas BMP con una grilla de cols x rows celdas desde el caracter first (el negro es transparente)
+        pub fn from_bmp_atlas(path: &str, cols: usize, rows: usize, first: u8) -> Result<Self, String> {
+            let surface = Surface::load_bmp(path).map_err(|e| format!("{}: {}", path, e))?;
+            let surface = surface.convert_format(PixelFormatEnum::RGB888).map_err(|e| format!("{}: {}", path, e))?;
+            let (width, height, pitch) = (surface.width() as usize, surface.height() as usize, surface.pitch() as usize);
+            let (cell_w, cell_h) = (width / cols, height / rows);
+            if cell_w == 0 || cell_h == 0 {
+                return Err(format!("{}: atlas demasiado chico para {}x{} celdas", path, cols, rows));
+            }
+
+            let mut glyphs = HashMap::new();
+            surface.with_lock(|pixels| {
+                for cell in 0..cols * rows {
+                    let code = first as usize + cell;
+                    if code > 255 {
+                        break;
+                    }
+                    let c = code as u8;
+                    let (ox, oy) = ((cell % cols) * cell_w, (cell / cols) * cell_h);
+                    let mut glyph = TextureT::new(&(c as char).to_string(), cell_w, cell_h);
+                    let mut used_w = 0;
+                    for y in 0..cell_h {
+                        for x in 0..cell_w {
+                            let offset = (oy + y) * pitch + (ox + x) * 4;
+                            let color = u32::from_le_bytes([pixels[offset], pixels[offset + 1], pixels[offset + 2], 0]);
+                            if color != 0 {
+                                glyph.set(x, y, color);
+                                used_w = used_w.max(x + 1);
+                            }
+                        }
+                    }
+                    // Ancho proporcional: se recorta la parte vacia de la derecha
+                    if used_w > 0 {
+                        let mut trimmed = TextureT::new(&glyph.name, used_w, cell_h);
+                        trimmed.pixels.copy_from_slice(&glyph.pixels[..used_w * cell_h]);
+                        glyphs.insert(c as char, trimmed);
+                    }
+                }
+            });
+            Ok(FontT { name: path.to_string(), glyphs, height: cell_h as i32, space_width: cell_w as i32 / 2, spacing: 1, upper_only: false })
+        }
+
+        fn glyph(&self, c: char) -> Option<&TextureT> {
+            let c = if self.upper_only { c.to_ascii_uppercase() } else { c };
+            self.glyphs.get(&c)
+        }
+
+        fn advance(&self, c: char) -> i32 {
+            match self.glyph(c) {
+                Some(glyph) => glyph.width as i32 + self.spacing,
+                None => self.space_width + self.spacing,
+            }
+        }
+
+        pub fn line_height(&self, scale: i32) -> i32 {
+            (self.height + LINE_SPACING) * scale
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+// Fuente del juego: el atlas si esta, despues la del WAD y si no la incluida
+pub fn load_font(wad: Option<&WadT>) -> FontT {
+    if std::path::Path::new(ATLAS_FONT_PATH).exists() {
+        match FontT::from_bmp_atlas(ATLAS_FONT_PATH, ATLAS_COLS, ATLAS_ROWS, b' ') {
+            Ok(font) => return font,
+            Err(e) => eprintln!("Error al cargar la fuente: {}", e),
+        }
+    }
+    if let Some(wad) = wad {
+        match FontT::from_wad(wad, WAD_FONT_PREFIX, WAD_FONT_FIRST, WAD_FONT_LAST) {
+            Ok(font) => return font,
+            Err(e) => eprintln!("Usando la fuente incluida: {}", e),
+        }
+    }
+    FontT::builtin()
+}
+
+// Ancho en pixeles de la linea mas larga del texto
+pub fn text_width(font: &FontT, text: &str, scale: i32) -> i32 {
+    text.lines()
+        .map(|line| (line.chars().map(|c| font.advance(c)).sum::<i32>() - font.spacing).max(0) * scale)
+        .max()
+        .unwrap_or(0)
+}
+
+// Multiplica el color del glifo por el pedido (conserva el sombreado de las fuentes del WAD)
+fn colorize(pixel: u32, color: Option<u32>) -> u32 {
+    let Some(color) = color else { return pixel; };
+    // Las fuentes de Doom son rojas: se usa el canal mas brillante y no la luminancia para no oscurecerlas
+    let level = ((pixel >> 16) & 0xFF).max((pixel >> 8) & 0xFF).max(pixel & 0xFF);
+    palette::rgb(((color >> 16) & 0xFF) * level / 255, ((color >> 8) & 0xFF) * level / 255, (color & 0xFF) * level / 255)
+}
+
+fn draw_glyph(screen: &mut Screen, glyph: &TextureT, x: i32, y: i32, style: &TextStyleT) {
+    let scale = style.scale.max(1);
+    for gx in 0..glyph.width {
+        for (gy, &pixel) in glyph.column(gx).iter().enumerate() {
+            if pixel == TRANSPARENT {
+                continue;
+            }
+            let color = colorize(pixel, style.color);
+            let (px, py) = (x + gx as i32 * scale, y + gy as i32 * scale);
+            for sy in 0..scale {
+                for sx in 0..scale {
+                    screen.put_pixel(px + sx, py + sy, color);
+                }
+            }
+        }
+    }
+}
+
+// Dibuja un texto (puede tener varias lineas); x es el borde izquierdo, el centro o el borde derecho segun la alineacion
+pub fn draw_text(screen: &mut Screen, font: &FontT, x: i32, y: i32, text: &str, style: &TextStyleT) {
+    let scale = style.scale.max(1);
+    for (row, line) in text.lines().enumerate() {
+        let width = text_width(font, line, scale);
+        let mut pen_x = match style.align {
+            AlignT::Left => x,
+            AlignT::Center => x - width / 2,
+            AlignT::Right => x - width,
+        };
+        let pen_y = y + row as i32 * font.line_height(scale);
+        for c in line.chars() {
+            if let Some(glyph) = font.glyph(c) {
+                draw_glyph(screen, glyph, pen_x, pen_y, style);
+            }
+            pen_x += font.advance(c) * scale;
+        }
+    }
+}
+
+// Texto con sombra para que se lea sobre cualquier fondo
+pub fn draw_shadowed(screen: &mut Screen, font: &FontT, x: i32, y: i32, text: &str, style: &TextStyleT) {
+    let offset = (style.scale / 2).max(1);
+    let shadow = TextStyleT { color: Some(SHADOW_CLR), ..*style };
+    draw_text(screen, font, x + offset, y + offset, text, &shadow);
+    draw_text(screen, font, x, y, text, style);
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use crate::{palette, renderer::SectorT, wad::{self, WadT}};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+// Marca de pixel transparente (los colores validos nunca usan el byte alto)
+pub const TRANSPARENT: u32 = 0xFF00_0000;
+pub const SKY_TEXTURE: &str = "SKY1";
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Imagen guardada por columnas (como los patches de Doom)
+#[derive(Clone)]
+pub struct TextureT {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub left_offset: i32,
+    pub top_offset: i32,
+    pub pixels: Vec<u32>,
+}
+    impl TextureT {
+        pub fn new(name: &str, width: usize, height: usize) -> Self {
+            TextureT {
+                name: name.to_string(),
+                width,
+                height,
+                left_offset: 0,
+                top_offset: 0,
+                pixels: vec![TRANSPARENT; width * height],
+            }
+        }
+
+        // Decodifica un patch de Doom (columnas de "posts" con huecos transparentes)
+        pub fn from_patch(name: &str, data: &[u8], base_palette: &[u32; 256]) -> Result<Self, String> {
+            if data.len() < 8 {
+                return Err(format!("patch '{}' demasiado corto", name));
+            }
+            // Se leen con signo: un tamaño negativo pasado a usize seria enorme
+            let (width, height) = (wad::read_i16(data, 0), wad::read_i16(data, 2));
+            if width <= 0 || height <= 0 || data.len() < 8 + width as usize * 4 {
+                return Err(format!("patch '{}' con cabecera invalida", name));
+            }
+            let (width, height) = (width as usize, height as usize);
+
+            let mut texture = TextureT::new(name, width, height);
+            texture.left_offset = wad::read_i16(data, 4) as i32;
+            texture.top_offset = wad::read_i16(data, 6) as i32;
+
+            for x in 0..width {
+                let mut pos = wad::read_i32(data, 8 + x * 4) as usize;
+                loop {
+                    let Some(&top_delta) = data.get(pos) else {
+                        return Err(format!("patch '{}' truncado en la columna {}", name, x));
+                    };
+                    if top_delta == 0xFF {
+                        break;
+                    }
+                    let length = *data.get(pos + 1).unwrap_or(&0) as usize;
+                    let start = pos + 3;
+                    if start + length > data.len() {
+                        return Err(format!("patch '{}' truncado en la columna {}", name, x));
+                    }
+                    for i in 0..length {
+                        let y = top_delta as usize + i;
+                        if y < height {
+                            texture.pixels[x * height + y] = base_palette[data[start + i] as usize];
+                        }
+                    }
+                    pos = start + length + 1;
+                }
+            }
+            Ok(texture)
+        }
+
+        pub fn column(&self, x: usize) -> &[u32] {
+            let x = x % self.width;
+            &self.pixels[x * self.height..(x + 1) * self.height]
+        }
+
+        pub fn set(&mut self, x: usize, y: usize, color: u32) {
+            self.pixels[x * self.height + y] = color;
+        }
+    }
+
+pub struct TexturesT {
+    pub textures: HashMap<String, TextureT>,
+}
+    impl TexturesT {
+        pub fn new() -> Self {
+            TexturesT { textures: HashMap::new() }
+        }
+
+        pub fn get(&self, name: &str) -> Option<&TextureT> {
+            self.textures.get(&name.to_ascii_uppercase())
+        }
+
+        pub fn insert(&mut self, texture: TextureT) {
+            self.textures.insert(texture.name.to_ascii_uppercase(), texture);
+        }
+
+        // Carga un patch del WAD por nombre de lump
+        pub fn load_patch(&mut self, wad: &WadT, name: &str) -> Result<(), String> {
+            let base = palette::base_palette(wad)?;
+            let data = wad.lump(name).ok_or(format!("{}: falta el lump {}", wad.path, name))?;
+            let texture = TextureT::from_patch(name, data, &base)?;
+            self.insert(texture);
+            Ok(())
+        }
+
+        // Busca una textura por nombre: primero en el WAD (TEXTURE1/2 o patch suelto), despues las generadas
+        pub fn load_named(&mut self, wad: Option<&WadT>, name: &str) -> Result<(), String> {
+            if self.get(name).is_some() {
+                return Ok(());
+            }
+            if let Some(wad) = wad {
+                let base = palette::base_palette(wad)?;
+                if let Some(texture) = compose_wad_texture(wad, name, &base)? {
+                    self.insert(texture);
+                    return Ok(());
+                }
+                if let Some(data) = wad.lump(name) {
+                    self.insert(TextureT::from_patch(name, data, &base)?);
+                    return Ok(());
+                }
+            }
+            match builtin_texture(name) {
+                Some(texture) => {
+                    self.insert(texture);
+                    Ok(())
+                }
+                None => Err(format!("textura '{}' no encontrada", name)),
+            }
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+
+// Cielo generado: degrade con nubes, para cuando no hay WAD
+pub fn sky_fallback() -> TextureT {
+    let (width, height) = (256, 128);
+    let mut sky = TextureT::new(SKY_TEXTURE, width, height);
+    for x in 0..width {
+        for y in 0..height {
+            let t = y as f64 / height as f64;
+            // Ruido barato y repetible: suma de senos que cierra en los bordes
+            let fx = x as f64 / width as f64 * std::f64::consts::TAU;
+            let cloud = ((fx * 3.0 + t * 5.0).sin() + (fx * 7.0 - t * 9.0).sin() * 0.5 + (fx * 2.0).cos()) / 2.5;
+            let cloud = ((cloud - 0.2) * 2.0).clamp(0.0, 1.0) * (1.0 - t);
+            let base = (40.0 + 80.0 * t, 70.0 + 90.0 * t, 150.0 + 70.0 * t);
+            let r = base.0 + (230.0 - base.0) * cloud;
+            let g = base.1 + (230.0 - base.1) * cloud;
+            let b = base.2 + (235.0 - base.2) * cloud;
+            sky.set(x, y, palette::rgb(r as u32, g as u32, b as u32));
+        }
+    }
+    sky
+}
+
+// Texturas enmascaradas generadas para usar sin WAD (rejas, alambrados y ventanas)
+pub fn builtin_texture(name: &str) -> Option<TextureT> {
+    let (width, height) = (64, 128);
+    let mut texture = TextureT::new(&name.to_ascii_uppercase(), width, height);
+    match name.to_ascii_uppercase().as_str() {
+        "GRATE" => {
+            for x in 0..width {
+                for y in 0..height {
+                    let bar = x % 16 < 4;
+                    let rail = y % 32 < 3;
+                    if bar || rail {
+                        let edge = if x % 16 == 0 || y % 32 == 0 { 0x909090 } else { 0x606060 };
+                        texture.set(x, y, edge);
+                    }
+                }
+            }
+        }
+        "FENCE" => {
+            for x in 0..width {
+                for y in 0..height {
+                    if (x + y) % 16 < 2 || (x + 16 * 8 - y) % 16 < 2 || y < 4 {
+                        texture.set(x, y, 0x9A9A88);
+                    }
+                }
+            }
+        }
+        "WINDOW" => {
+            for x in 0..width {
+                for y in 0..height {
+                    let frame = x < 4 || x >= width - 4 || y < 4 || y >= height - 4;
+                    let cross = (30..34).contains(&x) || (62..66).contains(&y);
+                    let color = if frame || cross { 0x5A3A20 } else { 0x80B0D0 };
+                    texture.set(x, y, color);
+                }
+            }
+        }
+        _ => return None,
+    }
+    Some(texture)
+}
+
+fn read_name(data: &[u8], offset: usize) -> String {
+    data[offset..offset + 8]
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| (c as char).to_ascii_uppercase())
+        .collect()
+}
+
+// Arma una textura compuesta de TEXTURE1/TEXTURE2 pegando sus patches (PNAMES)
+pub fn compose_wad_texture(wad: &WadT, name: &str, base_palette: &[u32; 256]) -> Result<Option<TextureT>, String> {
+    let Some(pnames) = wad.lump("PNAMES") else {
+        return Ok(None);
+    };
+    let name = name.to_ascii_uppercase();
+
+    for list in ["TEXTURE1", "TEXTURE2"] {
+        let Some(data) = wad.lump(list) else { continue; };
+        if data.len() < 4 {
+            return Err(format!("{}: lista demasiado corta", list));
+        }
+        let count = usize::try_from(wad::read_i32(data, 0)).ok()
+            .filter(|&count| count.checked_mul(4).and_then(|size| size.checked_add(4)).is_some_and(|end| end <= data.len()))
+            .ok_or(format!("{}: cantidad de texturas invalida", list))?;
+        for i in 0..count {
+            let offset = usize::try_from(wad::read_i32(data, 4 + i * 4)).ok()
+                .filter(|&offset| offset.checked_add(22).is_some_and(|end| end <= data.len()))
+                .ok_or(format!("{}: textura {} fuera de la lista", list, i))?;
+            if read_name(data, offset) != name {
+                continue;
+            }
+
+            // Tamaños negativos o en cero romperian la division por el ancho y la reserva de pixeles
+            let width = wad::read_i16(data, offset + 12);
+            let height = wad::read_i16(data, offset + 14);
+            let patch_count = wad::read_i16(data, offset + 20);
+            if width <= 0 || height <= 0 || patch_count <= 0 {
+                return Err(format!("{}: textura {} con cabecera invalida", list, name));
+            }
+            let (width, height, patch_count) = (width as usize, height as usize, patch_count as usize);
+            let mut texture = TextureT::new(&name, width, height);
+
+            for p in 0..patch_count {
+                let entry = offset + 22 + p * 10;
+                if entry + 10 > data.len() {
+                    return Err(format!("{}: textura {} truncada", list, name));
+                }
+                let origin_x = wad::read_i16(data, entry) as i32;
+                let origin_y = wad::read_i16(data, entry + 2) as i32;
+                let patch_index = wad::read_i16(data, entry + 4);
+                let name_offset = usize::try_from(patch_index).ok().map(|index| 4 + index * 8)
+                    .filter(|&name_offset| name_offset + 8 <= pnames.len())
+                    .ok_or(format!("{}: textura {} usa el patch {} que no esta en PNAMES", list, name, patch_index))?;
+                let patch_name = read_name(pnames, name_offset);
+                let patch_data = wad.lump(&patch_name).ok_or(format!("{}: falta el patch {}", wad.path, patch_name))?;
+                let patch = TextureT::from_patch(&patch_name, patch_data, base_palette)?;
+
+                for px in 0..patch.width {
+                    let tx = origin_x + px as i32;
+                    if tx < 0 || tx >= width as i32 {
+                        continue;
+                    }
+                    for (py, &color) in patch.column(px).iter().enumerate() {
+                        let ty = origin_y + py as i32;
+                        if color != TRANSPARENT && ty >= 0 && ty < height as i32 {
+                            texture.set(tx as usize, ty as usize, color);
+                        }
+                    }
+                }
+            }
+            return Ok(Some(texture));
+        }
+    }
+    Ok(None)
+}
+
+// Carga las texturas que usa el motor: del WAD si esta, si no las generadas
+pub fn load_textures(wad: Option<&WadT>) -> TexturesT {
+    let mut textures = TexturesT::new();
+    textures.insert(sky_fallback());
+    if let Some(wad) = wad && let Err(e) = textures.load_patch(wad, SKY_TEXTURE) {
+        eprintln!("Usando el cielo generado: {}", e);
+    }
+    textures
+}
+
+// Agrega las texturas que pide un mapa (las que ya estan cargadas se reusan)
+pub fn load_map_textures(textures: &mut TexturesT, wad: Option<&WadT>, sectors: &[SectorT]) {
+    for wall in sectors.iter().flat_map(|s| s.walls.iter()) {
+        if let Some(name) = &wall.mid_texture && let Err(e) = textures.load_named(wad, name) {
+            eprintln!("Error al cargar texturas del mapa: {}", e);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Vec2T {
     pub x: f64,
     pub y: f64,
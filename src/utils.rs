@@ -1,14 +1,38 @@
-use rand::Rng;
 use sdl2::Sdl;
 
 
 ///////////////////////////////// Algunas funciones utiles /////////////////////////////////
 
-// Genera un numero u32 aleatorio
-pub fn rand_range_ui(min: u32, max: u32) -> u32 {
-    let mut rng = rand::rng();
-    rng.random_range(min..max)  // Num aleatorio en el rango [min, max)
-} 
+// Generador del juego (xorshift): con la misma semilla se repite igual, asi las demos y
+// partidas guardadas no dependen del azar del sistema
+#[derive(Clone)]
+pub struct GameRngT {
+    pub state: u32,
+}
+    impl GameRngT {
+        pub fn new(seed: u32) -> Self {
+            GameRngT { state: seed.max(1) }
+        }
+
+        pub fn next(&mut self) -> u32 {
+            let mut x = self.state;
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            self.state = x;
+            x
+        }
+
+        // Num en el rango [min, max)
+        pub fn range(&mut self, min: i32, max: i32) -> i32 {
+            min + (self.next() % (max - min).max(1) as u32) as i32
+        }
+
+        // true con probabilidad chance (0..1)
+        pub fn chance(&mut self, chance: f64) -> bool {
+            (self.next() % 10000) as f64 / 10000.0 < chance
+        }
+    }
 
 
 // Muestra por pantalla si los contextos de SDL fueron bien inicializados
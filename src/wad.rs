@@ -0,0 +1,98 @@
+use std::fs;
+
+// WAD que se busca por defecto para los recursos opcionales
+pub const DEFAULT_WAD: &str = "assets/doom1.wad";
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+// Entrada del directorio de un WAD
+#[derive(Clone)]
+pub struct LumpT {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+pub struct WadT {
+    pub path: String,
+    pub is_iwad: bool,
+    pub lumps: Vec<LumpT>,
+    pub data: Vec<u8>,
+}
+    impl WadT {
+        // Lee el archivo completo y su directorio de lumps
+        pub fn load(path: &str) -> Result<Self, String> {
+            let data = fs::read(path).map_err(|e| format!("no se pudo leer '{}': {}", path, e))?;
+            Self::from_bytes(path, data)
+        }
+
+        pub fn from_bytes(path: &str, data: Vec<u8>) -> Result<Self, String> {
+            if data.len() < 12 {
+                return Err(format!("{}: archivo demasiado corto para ser un WAD", path));
+            }
+            let is_iwad = match &data[0..4] {
+                b"IWAD" => true,
+                b"PWAD" => false,
+                _ => return Err(format!("{}: no es un WAD (falta la firma IWAD/PWAD)", path)),
+            };
+            // Los valores negativos o que se pasan del archivo son de un WAD roto, no un panic
+            let (Ok(num_lumps), Ok(dir_offset)) = (usize::try_from(read_i32(&data, 4)), usize::try_from(read_i32(&data, 8))) else {
+                return Err(format!("{}: cabecera invalida", path));
+            };
+            let dir_end = num_lumps.checked_mul(16).and_then(|size| size.checked_add(dir_offset));
+            if dir_end.is_none_or(|end| end > data.len()) {
+                return Err(format!("{}: directorio fuera del archivo", path));
+            }
+
+            let mut lumps = Vec::with_capacity(num_lumps);
+            for i in 0..num_lumps {
+                let entry = dir_offset + i * 16;
+                let (Ok(offset), Ok(size)) = (usize::try_from(read_i32(&data, entry)), usize::try_from(read_i32(&data, entry + 4))) else {
+                    return Err(format!("{}: lump {} con posicion o tamaño negativo", path, i));
+                };
+                if offset.checked_add(size).is_none_or(|end| end > data.len()) {
+                    return Err(format!("{}: lump {} fuera del archivo", path, i));
+                }
+                lumps.push(LumpT { name: lump_name(&data[entry + 8..entry + 16]), offset, size });
+            }
+
+            Ok(WadT { path: path.to_string(), is_iwad, lumps, data })
+        }
+
+        // Indice del lump con ese nombre (el ultimo gana, como en Doom)
+        pub fn find_lump(&self, name: &str) -> Option<usize> {
+            let name = name.to_ascii_uppercase();
+            self.lumps.iter().rposition(|l| l.name == name)
+        }
+
+        pub fn lump_bytes(&self, index: usize) -> &[u8] {
+            let lump = &self.lumps[index];
+            &self.data[lump.offset..lump.offset + lump.size]
+        }
+
+        pub fn lump(&self, name: &str) -> Option<&[u8]> {
+            self.find_lump(name).map(|i| self.lump_bytes(i))
+        }
+
+        // Nombres de los niveles en el orden del WAD (cada marcador va seguido de su THINGS)
+        pub fn map_names(&self) -> Vec<String> {
+            self.lumps.windows(2).filter(|pair| pair[1].name == "THINGS").map(|pair| pair[0].name.clone()).collect()
+        }
+    }
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+pub fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+pub fn read_i16(data: &[u8], offset: usize) -> i16 {
+    i16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+// Los nombres son 8 bytes ASCII completados con ceros
+fn lump_name(raw: &[u8]) -> String {
+    raw.iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| (c as char).to_ascii_uppercase())
+        .collect()
+}
@@ -0,0 +1,353 @@
+use crate::{ai::{self, DiceT}, collision::{self, BodyT, RayT}, entities::{self, EntityIdT}, game_state::GameStateT, player::{self, PlayerT}, specials, sprites::SpriteThingT, typedefs::Vec2T};
+
+///////////////////////////////// CONSTANTES /////////////////////////////////
+pub const NUM_WEAPONS: usize = 6;
+pub const NUM_AMMO: usize = 4;
+
+const MELEE_RANGE: f64 = 64.0;
+const HITSCAN_RANGE: f64 = 2048.0;
+const AUTOAIM_ANGLE: f64 = 0.098; // Si no hay nadie enfrente se prueba ~5.6 grados a cada lado
+const SPREAD: f64 = 0.098;        // Desvio maximo de las balas
+pub const SWITCH_TICS: i32 = 6;   // Lo que tarda en bajar un arma y subir la otra
+const EXPLODE_TICS: i32 = 18;     // Duracion de la explosion de un proyectil
+const MUZZLE_HEIGHT: f64 = 8.0;   // Los proyectiles salen un poco por debajo de los ojos
+const FLASH_TICS: i32 = 4;        // Duracion del fogonazo en la vista
+
+pub const AMMO_NAMES: [&str; NUM_AMMO] = ["BULLETS", "SHELLS", "ROCKETS", "CELLS"];
+pub const MAX_AMMO: [i32; NUM_AMMO] = [200, 50, 50, 300];
+
+// Proyectiles (valores de Doom): el radio y la altura salen de la tabla de entidades
+pub static ROCKET: ProjectileInfoT = ProjectileInfoT { sprite: "MISL", speed: 20.0, damage: DiceT { sides: 8, mult: 20 }, splash: 128.0 };
+pub static PLASMA: ProjectileInfoT = ProjectileInfoT { sprite: "PLSS", speed: 25.0, damage: DiceT { sides: 8, mult: 5 }, splash: 0.0 };
+pub static IMP_BALL: ProjectileInfoT = ProjectileInfoT { sprite: "BAL1", speed: 10.0, damage: DiceT { sides: 8, mult: 3 }, splash: 0.0 };
+static PROJECTILES: [&ProjectileInfoT; 3] = [&ROCKET, &PLASMA, &IMP_BALL];
+
+// Armas en el orden de las teclas 1..6
+pub static WEAPONS: [WeaponInfoT; NUM_WEAPONS] = [
+    WeaponInfoT { name: "FIST", sprite: "PUNG", flash: None, ammo: None, ammo_per_shot: 0, refire_tics: 22, fire: FireT::Melee(DiceT { sides: 10, mult: 2 }) },
+    WeaponInfoT { name: "PISTOL", sprite: "PISG", flash: Some("PISF"), ammo: Some(AmmoT::Bullets), ammo_per_shot: 1, refire_tics: 14, fire: FireT::Hitscan(1, DiceT { sides: 3, mult: 5 }) },
+    WeaponInfoT { name: "SHOTGUN", sprite: "SHTG", flash: Some("SHTF"), ammo: Some(AmmoT::Shells), ammo_per_shot: 1, refire_tics: 37, fire: FireT::Hitscan(7, DiceT { sides: 3, mult: 5 }) },
+    WeaponInfoT { name: "CHAINGUN", sprite: "CHGG", flash: Some("CHGF"), ammo: Some(AmmoT::Bullets), ammo_per_shot: 1, refire_tics: 4, fire: FireT::Hitscan(1, DiceT { sides: 3, mult: 5 }) },
+    WeaponInfoT { name: "ROCKET LAUNCHER", sprite: "MISG", flash: Some("MISF"), ammo: Some(AmmoT::Rockets), ammo_per_shot: 1, refire_tics: 20, fire: FireT::Projectile(&ROCKET) },
+    WeaponInfoT { name: "PLASMA RIFLE", sprite: "PLSG", flash: Some("PLSF"), ammo: Some(AmmoT::Cells), ammo_per_shot: 1, refire_tics: 3, fire: FireT::Projectile(&PLASMA) },
+];
+
+///////////////////////////////// STRUCTS /////////////////////////////////
+#[derive(Clone, Copy, PartialEq)]
+pub enum AmmoT {
+    Bullets,
+    Shells,
+    Rockets,
+    Cells,
+}
+
+pub enum FireT {
+    Melee(DiceT),
+    Hitscan(i32, DiceT), // Perdigones por disparo y daño de cada uno
+    Projectile(&'static ProjectileInfoT),
+}
+
+pub struct WeaponInfoT {
+    pub name: &'static str,
+    pub sprite: &'static str,         // Sprite en primera persona (PISG -> PISGA0)
+    pub flash: Option<&'static str>,  // Fogonazo que se dibuja encima al disparar
+    pub ammo: Option<AmmoT>,
+    pub ammo_per_shot: i32,
+    pub refire_tics: i32, // Tics entre disparos
+    pub fire: FireT,
+}
+
+pub struct ProjectileInfoT {
+    pub sprite: &'static str,
+    pub speed: f64,    // Unidades por tic
+    pub damage: DiceT, // Daño del impacto directo
+    pub splash: f64,   // Daño maximo (y radio) de la explosion, 0 si no explota
+}
+
+// Armas y municion del jugador
+#[derive(Clone)]
+pub struct ArsenalT {
+    pub current: usize,
+    pub pending: Option<usize>, // Arma que se saca cuando termina la espera
+    pub owned: [bool; NUM_WEAPONS],
+    pub ammo: [i32; NUM_AMMO],
+    pub cooldown: i32,  // Tics hasta poder disparar o cambiar de arma
+    pub is_firing: bool, // Gatillo apretado
+    pub refire: bool,   // Sigue apretado desde el disparo anterior (el primer tiro es preciso)
+    pub flash: i32,     // Tics que le quedan al fogonazo
+    pub raise: i32,     // Tics que le faltan al arma nueva para terminar de subir
+}
+    impl ArsenalT {
+        // Como al empezar en Doom: puño, pistola y 50 balas
+        pub fn new() -> Self {
+            ArsenalT {
+                current: 1,
+                pending: None,
+                owned: [true, true, false, false, false, false],
+                ammo: [50, 0, 0, 0],
+                cooldown: 0,
+                is_firing: false,
+                refire: false,
+                flash: 0,
+                raise: 0,
+            }
+        }
+    }
+
+// Componente de los proyectiles en vuelo
+#[derive(Clone)]
+pub struct ProjectileT {
+    pub info: &'static ProjectileInfoT,
+    pub velocity: Vec2T,
+    pub vz: f64,
+    pub owner: Option<EntityIdT>, // Quien lo disparo (no choca con el)
+    pub from_player: bool,
+    pub explode_tics: Option<i32>, // Ya exploto: tics hasta desaparecer
+}
+
+
+///////////////////////////////// FUNCIONES /////////////////////////////////
+pub fn projectile_info(sprite: &str) -> Option<&'static ProjectileInfoT> {
+    PROJECTILES.iter().copied().find(|p| p.sprite == sprite)
+}
+
+pub fn has_ammo(arsenal: &ArsenalT, weapon: usize) -> bool {
+    let info = &WEAPONS[weapon];
+    info.ammo.is_none_or(|ammo| arsenal.ammo[ammo as usize] >= info.ammo_per_shot)
+}
+
+// Pide cambiar a un arma si la tiene y le queda municion
+pub fn select(arsenal: &mut ArsenalT, weapon: usize) {
+    if weapon < NUM_WEAPONS && arsenal.owned[weapon] && has_ammo(arsenal, weapon) && weapon != arsenal.current {
+        arsenal.pending = Some(weapon);
+    }
+}
+
+// Siguiente (o anterior) arma que se puede usar
+pub fn cycle(arsenal: &ArsenalT, step: i32) -> Option<usize> {
+    let start = arsenal.pending.unwrap_or(arsenal.current) as i32;
+    (1..NUM_WEAPONS as i32).map(|i| (start + i * step).rem_euclid(NUM_WEAPONS as i32) as usize)
+        .find(|&w| arsenal.owned[w] && has_ammo(arsenal, w))
+}
+
+// La mejor arma con municion, para cuando se acaba la actual (como P_CheckAmmo)
+fn best_weapon(arsenal: &ArsenalT) -> usize {
+    (0..NUM_WEAPONS).rev().find(|&w| arsenal.owned[w] && has_ammo(arsenal, w)).unwrap_or(0)
+}
+
+// Autoapuntado vertical: pendiente hacia el monstruo vivo mas cercano en la linea de tiro
+fn aim_slope(state: &GameStateT, player: &PlayerT, range: f64) -> f64 {
+    for offset in [0.0, AUTOAIM_ANGLE, -AUTOAIM_ANGLE] {
+        let angle = player.dir_angle + offset;
+        let (dx, dy) = (angle.cos(), angle.sin());
+        let target = state.entities.iter().filter(|e| ai::is_alive(e)).filter_map(|e| {
+            let (cx, cy) = (e.thing.position.x - player.position.x, e.thing.position.y - player.position.y);
+            let along = cx * dx + cy * dy;
+            let in_line = along > 0.0 && along < range && (cx * dy - cy * dx).abs() <= e.radius;
+            let visible = in_line && collision::check_sight(&state.sectors, &player.position, player.z, &e.thing.position, e.thing.z, e.thing.z + e.height);
+            visible.then(|| (along, (e.thing.z + e.height * 0.5 - player.z) / along))
+        }).min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, slope)) = target {
+            return slope;
+        }
+    }
+    0.0
+}
+
+fn hit(state: &mut GameStateT, player: &mut PlayerT, ray: &RayT, dice: DiceT) {
+    let hit = collision::trace(&state.sectors, &state.entities, ray, None);
+    let amount = ai::roll(state, dice);
+    if let Some(id) = hit.thing {
+        ai::damage_monster(state, player, id, amount);
+    } else if let Some((sector, wall)) = hit.wall {
+        specials::shoot_line(state, sector, wall);
+    }
+}
+
+fn fire(state: &mut GameStateT, player: &mut PlayerT, weapon: usize) {
+    let info = &WEAPONS[weapon];
+    match info.fire {
+        FireT::Melee(dice) => {
+            let ray = RayT { origin: player.position.clone(), z: player.z, angle: player.dir_angle, slope: aim_slope(state, player, MELEE_RANGE), range: MELEE_RANGE };
+            hit(state, player, &ray, dice);
+            return; // Los golpes no hacen ruido
+        }
+        FireT::Hitscan(pellets, dice) => {
+            let slope = aim_slope(state, player, HITSCAN_RANGE);
+            for _ in 0..pellets {
+                // El primer tiro de la pistola o la ametralladora va derecho
+                let spread = if pellets > 1 || player.weapons.refire {
+                    (state.rng.range(0, 256) - state.rng.range(0, 256)) as f64 / 255.0 * SPREAD
+                } else {
+                    0.0
+                };
+                let ray = RayT { origin: player.position.clone(), z: player.z, angle: player.dir_angle + spread, slope, range: HITSCAN_RANGE };
+                hit(state, player, &ray, dice);
+            }
+        }
+        FireT::Projectile(projectile) => {
+            let slope = aim_slope(state, player, HITSCAN_RANGE);
+            spawn_projectile(state, projectile, &player.position.clone(), player.z - MUZZLE_HEIGHT, player.dir_angle, slope, None);
+        }
+    }
+    if let Some(sector) = state.sector_at(player.position.x, player.position.y) {
+        ai::noise_alert(state, sector);
+    }
+}
+
+// Un tic del arma del jugador: cambios, cadencia y consumo de municion
+fn tick_weapon(state: &mut GameStateT, player: &mut PlayerT) {
+    let arsenal = &mut player.weapons;
+    arsenal.flash = (arsenal.flash - 1).max(0);
+    arsenal.raise = (arsenal.raise - 1).max(0);
+    if arsenal.cooldown > 0 {
+        arsenal.cooldown -= 1;
+        return;
+    }
+    if let Some(next) = arsenal.pending.take() {
+        arsenal.current = next;
+        arsenal.cooldown = SWITCH_TICS;
+        arsenal.raise = SWITCH_TICS;
+        return;
+    }
+    if !arsenal.is_firing {
+        arsenal.refire = false;
+        return;
+    }
+    let weapon = arsenal.current;
+    if !has_ammo(arsenal, weapon) {
+        select(arsenal, best_weapon(arsenal));
+        return;
+    }
+    let info = &WEAPONS[weapon];
+    if let Some(ammo) = info.ammo {
+        arsenal.ammo[ammo as usize] -= info.ammo_per_shot;
+    }
+    arsenal.cooldown = info.refire_tics;
+    if info.flash.is_some() {
+        arsenal.flash = FLASH_TICS;
+    }
+    fire(state, player, weapon);
+    player.weapons.refire = true;
+}
+
+// Crea un proyectil centrado en z que sale con un angulo y una pendiente (sin dueño lo disparo el jugador)
+pub fn spawn_projectile(state: &mut GameStateT, info: &'static ProjectileInfoT, from: &Vec2T, z: f64, angle: f64, slope: f64, owner: Option<EntityIdT>) {
+    let Some(sector) = state.sector_at(from.x, from.y) else { return; };
+    let thing = SpriteThingT::new(from.x, from.y, z, angle, info.sprite, 'A');
+    let id = state.entities.spawn(thing, sector);
+    let Some(entity) = state.entities.get_mut(id) else { return; };
+    entity.thing.z -= entity.height * 0.5;
+    entity.projectile = Some(ProjectileT {
+        info,
+        velocity: Vec2T::new(angle.cos() * info.speed, angle.sin() * info.speed),
+        vz: slope * info.speed,
+        owner,
+        from_player: owner.is_none(),
+        explode_tics: None,
+    });
+}
+
+// Daño en area (como P_RadiusAttack): baja con la distancia y no atraviesa paredes
+fn radius_attack(state: &mut GameStateT, player: &mut PlayerT, center: &Vec2T, z: f64, splash: f64) {
+    let reaches = |state: &GameStateT, position: &Vec2T, radius: f64, bottom: f64, top: f64| -> Option<i32> {
+        let dist = ((position.x - center.x).abs().max((position.y - center.y).abs()) - radius).max(0.0);
+        let amount = (splash - dist) as i32;
+        (amount > 0 && collision::check_sight(&state.sectors, center, z, position, bottom, top)).then_some(amount)
+    };
+    let victims: Vec<(EntityIdT, i32)> = state.entities.iter().filter(|e| ai::is_alive(e))
+        .filter_map(|e| reaches(state, &e.thing.position, e.radius, e.thing.z, e.thing.z + e.height).map(|amount| (e.id, amount)))
+        .collect();
+    for (id, amount) in victims {
+        ai::damage_monster(state, player, id, amount);
+    }
+    let feet = player.z - player::EYE_HEIGHT;
+    if let Some(amount) = reaches(state, &player.position, collision::PLAYER_RADIUS, feet, feet + collision::PLAYER_HEIGHT) {
+        player::damage(player, amount);
+    }
+}
+
+// Choca: daño directo, explosion y queda mostrando los cuadros de la explosion
+fn explode(state: &mut GameStateT, player: &mut PlayerT, id: EntityIdT, target: Option<EntityIdT>, hits_player: bool) {
+    let Some(entity) = state.entities.get_mut(id) else { return; };
+    let Some(projectile) = &mut entity.projectile else { return; };
+    let info = projectile.info;
+    projectile.explode_tics = Some(EXPLODE_TICS);
+    entity.thing.frame = 'B';
+    let (center, z) = (entity.thing.position.clone(), entity.thing.z + entity.height * 0.5);
+
+    let amount = ai::roll(state, info.damage);
+    if let Some(target) = target {
+        ai::damage_monster(state, player, target, amount);
+    }
+    if hits_player {
+        player::damage(player, amount);
+    }
+    if info.splash > 0.0 {
+        radius_attack(state, player, &center, z, info.splash);
+    }
+}
+
+fn tick_projectile(state: &mut GameStateT, player: &mut PlayerT, id: EntityIdT) {
+    let Some(entity) = state.entities.get_mut(id) else { return; };
+    let Some(projectile) = &mut entity.projectile else { return; };
+    if let Some(tics) = &mut projectile.explode_tics {
+        *tics -= 1;
+        if *tics <= 0 {
+            state.entities.remove(id);
+        }
+        return;
+    }
+
+    // Avanza en pasos cortos para no atravesar paredes ni things finos
+    let (velocity, vz, owner, from_player) = (projectile.velocity.clone(), projectile.vz, projectile.owner, projectile.from_player);
+    let steps = (velocity.x.hypot(velocity.y) / entity.radius).ceil().max(1.0) as usize;
+    let mut from = entity.thing.position.clone();
+    let mut feet_z = entity.thing.z;
+    let (radius, height) = (entity.radius, entity.height);
+    for _ in 0..steps {
+        let to = Vec2T::new(from.x + velocity.x / steps as f64, from.y + velocity.y / steps as f64);
+        feet_z += vz / steps as f64;
+        let body = BodyT { radius, height, feet_z, ignore: owner };
+        let Some(sector) = entities::find_sector(&state.sectors, to.x, to.y) else {
+            explode(state, player, id, None, false);
+            return;
+        };
+        // Contra el cielo desaparece sin explotar
+        let s = &state.sectors[sector];
+        if s.is_sky && feet_z + height > s.ceil_z() {
+            state.entities.remove(id);
+            return;
+        }
+        if feet_z < s.floor_z() || feet_z + height > s.ceil_z() || !collision::check_walls(&state.sectors, &to, &body) {
+            explode(state, player, id, None, false);
+            return;
+        }
+        if let Some(target) = collision::blocking_thing(&state.sectors, &state.entities, &from, &to, &body) {
+            explode(state, player, id, Some(target), false);
+            return;
+        }
+        let player_feet = player.z - player::EYE_HEIGHT;
+        let touches_player = collision::distance(&to, &player.position) < radius + collision::PLAYER_RADIUS
+            && feet_z < player_feet + collision::PLAYER_HEIGHT && feet_z + height > player_feet;
+        if !from_player && touches_player && !player::is_dead(player) {
+            explode(state, player, id, None, true);
+            return;
+        }
+        if let Some(entity) = state.entities.get_mut(id) {
+            entity.thing.position = to.clone();
+            entity.thing.z = feet_z;
+        }
+        state.entities.relink(id, sector);
+        from = to;
+    }
+}
+
+// Avanza un tic el arma del jugador y todos los proyectiles
+pub fn tick(state: &mut GameStateT, player: &mut PlayerT) {
+    tick_weapon(state, player);
+    let ids: Vec<EntityIdT> = state.entities.iter().filter(|e| e.projectile.is_some()).map(|e| e.id).collect();
+    for id in ids {
+        tick_projectile(state, player, id);
+    }
+}